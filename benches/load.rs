@@ -0,0 +1,147 @@
+/*
+ This Source Code Form is subject to the terms of the Mozilla Public
+ License, v. 2.0. If a copy of the MPL was not distributed with this
+ file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+//! Compare loading a large generated catalog one table after the
+//! other and with `Catalog::load_all()`.
+
+use std::path::Path;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use rusqlite::{params, Connection};
+
+use lrcat::Catalog;
+
+const FOLDERS: i64 = 1_000;
+const IMAGES_PER_FOLDER: i64 = 50;
+const KEYWORDS: i64 = 5_000;
+const COLLECTIONS: i64 = 500;
+
+/// Generate a catalog at `path` from the test catalog schema.
+fn generate(path: &Path) -> rusqlite::Result<()> {
+    let mut conn = Connection::open(path)?;
+    conn.execute_batch(include_str!("../data/test_catalog.sql"))?;
+
+    let tx = conn.transaction()?;
+    let mut id = 1_000;
+    let mut next_id = || {
+        id += 1;
+        id
+    };
+    let uuid = |id: i64| format!("00000000-0000-4000-8000-{id:012}");
+    for n in 0..KEYWORDS {
+        let id = next_id();
+        tx.execute(
+            "INSERT INTO AgLibraryKeyword (id_local, id_global, dateCreated, genealogy, \
+             lc_name, name, parent) VALUES (?1, ?2, 400000000.0, ?3, ?4, ?4, 1)",
+            params![id, uuid(id), format!("/11/{id}"), format!("keyword {n}")],
+        )?;
+    }
+    for n in 0..FOLDERS {
+        let folder = next_id();
+        tx.execute(
+            "INSERT INTO AgLibraryFolder (id_local, id_global, pathFromRoot, rootFolder) \
+             VALUES (?1, ?2, ?3, 10)",
+            params![folder, uuid(folder), format!("{}/{n}/", 2000 + n % 20)],
+        )?;
+        let content = next_id();
+        tx.execute(
+            "INSERT INTO AgFolderContent (id_local, id_global, containingFolder, content, \
+             owningModule) VALUES (?1, ?2, ?3, 'captureTime', 'com.adobe.ag.library.sortType')",
+            params![content, uuid(content), folder],
+        )?;
+        for i in 0..IMAGES_PER_FOLDER {
+            let file = next_id();
+            tx.execute(
+                "INSERT INTO AgLibraryFile (id_local, id_global, baseName, extension, folder, \
+                 idx_filename, originalFilename, sidecarExtensions) \
+                 VALUES (?1, ?2, ?3, 'CR2', ?4, ?5, ?5, 'xmp')",
+                params![
+                    file,
+                    uuid(file),
+                    format!("IMG_{i:04}"),
+                    folder,
+                    format!("IMG_{i:04}.CR2")
+                ],
+            )?;
+            let image = next_id();
+            tx.execute(
+                "INSERT INTO Adobe_images (id_local, id_global, captureTime, fileFormat, \
+                 orientation, pick, rating, rootFile) \
+                 VALUES (?1, ?2, '2017-10-01T10:00:00', 'RAW', 'AB', 0.0, 3, ?3)",
+                params![image, uuid(image), file],
+            )?;
+            let meta = next_id();
+            tx.execute(
+                "INSERT INTO Adobe_AdditionalMetadata (id_local, id_global, embeddedXmp, \
+                 externalXmpIsDirty, image, xmp) VALUES (?1, ?2, 0, 0, ?3, '<x:xmpmeta/>')",
+                params![meta, uuid(meta), image],
+            )?;
+            let props = next_id();
+            tx.execute(
+                "INSERT INTO Adobe_imageProperties (id_local, id_global, image, \
+                 propertiesString) VALUES (?1, ?2, ?3, \
+                 'properties = { cropAspectH = 2, cropAspectW = 3, }')",
+                params![props, uuid(props), image],
+            )?;
+        }
+    }
+    for n in 0..COLLECTIONS {
+        let collection = next_id();
+        tx.execute(
+            "INSERT INTO AgLibraryCollection (id_local, creationId, genealogy, name, parent, \
+             systemOnly) VALUES (?1, 'com.adobe.ag.library.collection', ?2, ?3, NULL, 0)",
+            params![
+                collection,
+                format!("/4{collection}"),
+                format!("collection {n}")
+            ],
+        )?;
+        let content = next_id();
+        tx.execute(
+            "INSERT INTO AgLibraryCollectionContent (id_local, collection, content, \
+             owningModule) VALUES (?1, ?2, 'captureTime', 'com.adobe.ag.library.sortType')",
+            params![content, collection],
+        )?;
+    }
+    tx.commit()
+}
+
+fn open(path: &Path) -> Catalog {
+    let mut catalog = Catalog::new(path);
+    catalog.open_read_only().unwrap();
+    catalog
+}
+
+fn bench_load(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("large.lrcat");
+    generate(&path).expect("Couldn't generate catalog");
+
+    let mut group = c.benchmark_group("load");
+    group.sample_size(10);
+    group.bench_function("serial", |b| {
+        b.iter(|| {
+            let mut catalog = open(&path);
+            catalog.load_keywords().unwrap();
+            catalog.load_folders().unwrap();
+            catalog.load_library_files().unwrap();
+            catalog.load_images().unwrap();
+            catalog.load_collections().unwrap();
+            catalog
+        })
+    });
+    group.bench_function("load_all", |b| {
+        b.iter(|| {
+            let mut catalog = open(&path);
+            catalog.load_all(|_| {}).unwrap();
+            catalog
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_load);
+criterion_main!(benches);
@@ -0,0 +1,132 @@
+/*
+ This Source Code Form is subject to the terms of the Mozilla Public
+ License, v. 2.0. If a copy of the MPL was not distributed with this
+ file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+//! Compare the borrowed lron parser with an allocating parser on a
+//! synthetic corpus looking like develop settings.
+//!
+//! `lron::Value::from_string()` is now the borrowed parser followed
+//! by `into_owned()`, so `reference` keeps a copy of the grammar that
+//! allocates every key and string while parsing, like it used to.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use lrcat::lron;
+
+/// The allocating parser, for reference.
+mod reference {
+    use lrcat::lron::{Pair, Value};
+
+    peg::parser! {pub grammar lron() for str {
+
+    use std::str::FromStr;
+
+    pub rule root() -> Value
+            = key:identifier() _() "=" _() value:array() _()
+        { Value::Pair(Box::new(Pair { key, value: Value::Dict(value) })) }
+
+    rule array() -> Vec<Value>
+            = "{" _() v:(entry() ** (_() "," _())) _()(",")? _() "}" { v }
+
+    rule entry() -> Value
+            = p:pair() { Value::Pair(Box::new(p)) } /
+            value()
+
+    rule pair() -> Pair
+            = key:identifier() _() "=" _() value:value() { Pair { key, value } } /
+            "[" key:string_literal() "]" _() "=" _() value:value()
+        { Pair { key, value } }
+
+    rule value() -> Value
+            = i:int() { Value::Int(i) } /
+            b:bool() { Value::Bool(b) } /
+            f:float() { Value::Float(f) } /
+            s:string_literal() { Value::Str(s) } /
+            a:array() { Value::Dict(a) } /
+            z:zstr() { Value::ZStr(z) }
+
+    rule int() -> i32
+            = n:$("-"? ['0'..='9']+) !"." { i32::from_str(n).unwrap() }
+
+    rule bool() -> bool
+            = "true" { true } / "false" { false }
+
+    rule float() -> f64
+            = f:$("-"? ['0'..='9']+ "." ['0'..='9']+) { f64::from_str(f).unwrap() }
+
+    rule identifier() -> String
+            = s:$(['a'..='z' | 'A'..='Z' | '0'..='9' | '_']+) { s.to_owned() }
+
+    rule escape() -> &'static str
+            = "\\\"" { "\"" } / "\\\n" { "\n" }
+
+    rule string_literal() -> String
+            = "\"" s:((escape() / $(!['"'][_]))*) "\"" { s.join("") }
+
+    rule zstr() -> String
+            = "ZSTR" _() s:string_literal() { s }
+
+    rule _() = quiet!{[' ' | '\r' | '\n' | '\t']*}
+
+    }}
+}
+
+/// Generate `count` develop settings like lron strings.
+fn corpus(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|n| {
+            let mut s = String::from("s = {\n");
+            for i in 0..60 {
+                s += &format!("\tSetting{i} = {}.{},\n", n % 100, i);
+                s += &format!("\tSettingName{i} = \"Value {n} {i}\",\n");
+            }
+            s += "\tProcessVersion = \"11.0\",\n\tWhiteBalance = \"As Shot\",\n";
+            s += "\tToneCurveName2012 = \"Linear\",\n\tToneCurvePV2012 = { 0, 0, 255, 255 },\n";
+            s += &format!(
+                "\tlook = {{\n\t\tName = ZSTR \"$$$/Look/{n}\",\n\t\tAmount = 1.0,\n\t}},\n"
+            );
+            s += "}\n";
+            s
+        })
+        .collect()
+}
+
+fn bench_parsers(c: &mut Criterion) {
+    let corpus = corpus(1000);
+    // Both parsers must agree.
+    assert_eq!(
+        reference::lron::root(&corpus[0]).unwrap(),
+        lron::Value::from_string(&corpus[0]).unwrap()
+    );
+    let bytes = corpus.iter().map(String::len).sum::<usize>();
+
+    let mut group = c.benchmark_group("lron");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function("allocating", |b| {
+        b.iter(|| {
+            for text in &corpus {
+                black_box(reference::lron::root(text).unwrap());
+            }
+        })
+    });
+    group.bench_function("borrowed", |b| {
+        b.iter(|| {
+            for text in &corpus {
+                black_box(lron::borrowed::Value::from_string(text).unwrap());
+            }
+        })
+    });
+    group.bench_function("borrowed_into_owned", |b| {
+        b.iter(|| {
+            for text in &corpus {
+                black_box(lron::Value::from_string(text).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_parsers);
+criterion_main!(benches);
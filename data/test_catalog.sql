@@ -0,0 +1,237 @@
+-- A minimal Lightroom 6 catalog used for the tests.
+-- Only the tables and columns used by the crate are created, with
+-- the same declarations as in Lightroom.
+
+CREATE TABLE Adobe_variablesTable (
+    id_local INTEGER PRIMARY KEY,
+    id_global UNIQUE NOT NULL,
+    name,
+    type,
+    value NOT NULL DEFAULT ''
+);
+
+CREATE TABLE AgLibraryKeyword (
+    id_local INTEGER PRIMARY KEY,
+    id_global UNIQUE NOT NULL,
+    dateCreated NOT NULL DEFAULT '',
+    genealogy NOT NULL DEFAULT '',
+    imageCountCache DEFAULT -1,
+    includeOnExport INTEGER NOT NULL DEFAULT 1,
+    includeParents INTEGER NOT NULL DEFAULT 1,
+    includeSynonyms INTEGER NOT NULL DEFAULT 1,
+    keywordType,
+    lastApplied,
+    lc_name,
+    name,
+    parent INTEGER
+);
+
+CREATE TABLE AgLibraryRootFolder (
+    id_local INTEGER PRIMARY KEY,
+    id_global UNIQUE NOT NULL,
+    absolutePath UNIQUE NOT NULL DEFAULT '',
+    name NOT NULL DEFAULT '',
+    relativePathFromCatalog
+);
+
+CREATE TABLE AgLibraryFolder (
+    id_local INTEGER PRIMARY KEY,
+    id_global UNIQUE NOT NULL,
+    parentId INTEGER,
+    pathFromRoot NOT NULL DEFAULT '',
+    rootFolder INTEGER NOT NULL DEFAULT 0,
+    visibility INTEGER
+);
+
+CREATE TABLE AgFolderContent (
+    id_local INTEGER PRIMARY KEY,
+    id_global UNIQUE NOT NULL,
+    containingFolder INTEGER NOT NULL DEFAULT 0,
+    content,
+    name,
+    owningModule
+);
+
+CREATE TABLE AgLibraryFile (
+    id_local INTEGER PRIMARY KEY,
+    id_global UNIQUE NOT NULL,
+    baseName NOT NULL DEFAULT '',
+    errorMessage,
+    errorTime,
+    extension NOT NULL DEFAULT '',
+    externalModTime,
+    folder INTEGER NOT NULL DEFAULT 0,
+    idx_filename NOT NULL DEFAULT '',
+    importHash,
+    lc_idx_filename NOT NULL DEFAULT '',
+    lc_idx_filenameExtension NOT NULL DEFAULT '',
+    md5,
+    modTime,
+    originalFilename NOT NULL DEFAULT '',
+    sidecarExtensions
+);
+
+CREATE TABLE Adobe_images (
+    id_local INTEGER PRIMARY KEY,
+    id_global UNIQUE NOT NULL,
+    aspectRatioCache NOT NULL DEFAULT -1,
+    bitDepth NOT NULL DEFAULT 0,
+    captureTime,
+    colorChannels NOT NULL DEFAULT 0,
+    colorLabels NOT NULL DEFAULT '',
+    colorMode NOT NULL DEFAULT -1,
+    copyCreationTime NOT NULL DEFAULT -63113817600,
+    copyName,
+    copyReason,
+    developSettingsIDCache,
+    fileFormat NOT NULL DEFAULT 'unset',
+    fileHeight,
+    fileWidth,
+    hasMissingSidecars INTEGER,
+    masterImage INTEGER,
+    orientation,
+    originalCaptureTime,
+    originalRootEntity INTEGER,
+    panningDistanceH,
+    panningDistanceV,
+    pick NOT NULL DEFAULT 0,
+    positionInFolder NOT NULL DEFAULT 'z',
+    propertiesCache,
+    pyramidIDCache,
+    rating,
+    rootFile INTEGER NOT NULL DEFAULT 0,
+    sidecarStatus,
+    touchCount NOT NULL DEFAULT 0,
+    touchTime NOT NULL DEFAULT 0
+);
+
+CREATE TABLE Adobe_AdditionalMetadata (
+    id_local INTEGER PRIMARY KEY,
+    id_global UNIQUE NOT NULL,
+    additionalInfoSet INTEGER NOT NULL DEFAULT 0,
+    embeddedXmp INTEGER NOT NULL DEFAULT 0,
+    externalXmpIsDirty INTEGER NOT NULL DEFAULT 0,
+    image INTEGER,
+    incrementalWhiteBalance INTEGER NOT NULL DEFAULT 0,
+    internalXmpDigest,
+    isRawFile INTEGER NOT NULL DEFAULT 0,
+    lastSynchronizedHash,
+    lastSynchronizedTimestamp NOT NULL DEFAULT -63113817600,
+    metadataPresetID,
+    metadataVersion,
+    monochrome INTEGER NOT NULL DEFAULT 0,
+    xmp NOT NULL DEFAULT ''
+);
+
+CREATE TABLE Adobe_imageProperties (
+    id_local INTEGER PRIMARY KEY,
+    id_global UNIQUE NOT NULL,
+    image INTEGER,
+    propertiesString NOT NULL DEFAULT ''
+);
+
+CREATE TABLE AgLibraryCollection (
+    id_local INTEGER PRIMARY KEY,
+    creationId NOT NULL DEFAULT '',
+    genealogy NOT NULL DEFAULT '',
+    imageCount,
+    name NOT NULL DEFAULT '',
+    parent INTEGER,
+    systemOnly NOT NULL DEFAULT ''
+);
+
+CREATE TABLE AgLibraryCollectionImage (
+    id_local INTEGER PRIMARY KEY,
+    collection INTEGER NOT NULL DEFAULT 0,
+    image INTEGER NOT NULL DEFAULT 0,
+    pick NOT NULL DEFAULT 0,
+    positionInCollection
+);
+
+CREATE TABLE AgLibraryCollectionContent (
+    id_local INTEGER PRIMARY KEY,
+    collection INTEGER NOT NULL DEFAULT 0,
+    content,
+    owningModule
+);
+
+CREATE TABLE AgLibraryImport (
+    id_local INTEGER PRIMARY KEY,
+    imageCount,
+    importDate NOT NULL DEFAULT '',
+    name
+);
+
+CREATE TABLE AgLibraryImportImage (
+    id_local INTEGER PRIMARY KEY,
+    image INTEGER NOT NULL DEFAULT 0,
+    import INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO Adobe_variablesTable (id_local, id_global, name, value) VALUES
+    (1, 'A7E4ED5C-0A44-4D38-A3B6-5A0C4E5B0D01', 'Adobe_DBVersion', '0600008'),
+    (2, 'A7E4ED5C-0A44-4D38-A3B6-5A0C4E5B0D02', 'AgLibraryKeyword_rootTagID', 1);
+
+INSERT INTO AgLibraryKeyword (id_local, id_global, dateCreated, genealogy, lc_name, name, parent) VALUES
+    (1, 'B1C1D1E1-0000-4000-8000-000000000001', 400000000.0, '/11', NULL, NULL, NULL),
+    (2, 'B1C1D1E1-0000-4000-8000-000000000002', 400000000.0, '/11/12', 'places', 'Places', 1),
+    (3, 'B1C1D1E1-0000-4000-8000-000000000003', 400000000.0, '/11/12/13', 'montréal', 'Montréal', 2),
+    (4, 'B1C1D1E1-0000-4000-8000-000000000004', 400000000.0, '/11/14', 'people', 'People', 1);
+
+INSERT INTO AgLibraryRootFolder (id_local, id_global, absolutePath, name, relativePathFromCatalog) VALUES
+    (10, 'C1C1D1E1-0000-4000-8000-000000000010', '/home/user/Pictures/', 'Pictures', '../Pictures/');
+
+INSERT INTO AgLibraryFolder (id_local, id_global, pathFromRoot, rootFolder) VALUES
+    (11, 'C1C1D1E1-0000-4000-8000-000000000011', '', 10),
+    (12, 'C1C1D1E1-0000-4000-8000-000000000012', '2017/', 10),
+    (13, 'C1C1D1E1-0000-4000-8000-000000000013', '2017/10/', 10);
+
+INSERT INTO AgFolderContent (id_local, id_global, containingFolder, content, owningModule) VALUES
+    (14, 'C1C1D1E1-0000-4000-8000-000000000014', 13, 'fileName', 'com.adobe.ag.library.sortType');
+
+INSERT INTO AgLibraryFile (id_local, id_global, baseName, extension, externalModTime, folder, idx_filename, importHash, lc_idx_filename, lc_idx_filenameExtension, md5, originalFilename, sidecarExtensions) VALUES
+    (20, 'D1C1D1E1-0000-4000-8000-000000000020', 'IMG_0001', 'CR2', 529000000.0, 13, 'IMG_0001.CR2', '2017-10-01T10:00:00-1', 'img_0001.cr2', 'cr2', NULL, 'IMG_0001.CR2', 'JPG,xmp'),
+    (21, 'D1C1D1E1-0000-4000-8000-000000000021', 'IMG_0002', 'JPG', 529000000.0, 13, 'IMG_0002.JPG', '2017-10-01T10:00:01-1', 'img_0002.jpg', 'jpg', NULL, 'IMG_0002.JPG', ''),
+    (22, 'D1C1D1E1-0000-4000-8000-000000000022', 'DSC_0003', 'NEF', 529000000.0, 12, 'DSC_0003.NEF', '2017-10-02T10:00:00-1', 'dsc_0003.nef', 'nef', NULL, 'DSC_0003.NEF', 'xmp'),
+    (23, 'D1C1D1E1-0000-4000-8000-000000000023', 'DSC_0004', 'DNG', 529000000.0, 12, 'DSC_0004.DNG', '2017-10-02T10:00:01-1', 'dsc_0004.dng', 'dng', NULL, 'DSC_0004.DNG', NULL);
+
+-- 30 has all its rows, 31 has no metadata, 32 has no properties,
+-- 33 has neither.
+INSERT INTO Adobe_images (id_local, id_global, captureTime, fileFormat, orientation, pick, rating, rootFile) VALUES
+    (30, 'E1C1D1E1-0000-4000-8000-000000000030', '2017-10-01T10:00:00', 'RAW', 'AB', 1.0, 5, 20),
+    (31, 'E1C1D1E1-0000-4000-8000-000000000031', '2017-10-01T10:00:01', 'JPG', 'BC', 0.0, NULL, 21),
+    (32, 'E1C1D1E1-0000-4000-8000-000000000032', '2017-10-02T10:00:00', 'RAW', 'AB', -1.0, 2, 22),
+    (33, 'E1C1D1E1-0000-4000-8000-000000000033', '2017-10-02T10:00:01', 'DNG', 'AB', 0.0, NULL, 23);
+
+INSERT INTO Adobe_AdditionalMetadata (id_local, id_global, embeddedXmp, externalXmpIsDirty, image, xmp) VALUES
+    (34, 'E1C1D1E1-0000-4000-8000-000000000034', 0, 0, 30, '<x:xmpmeta xmlns:x="adobe:ns:meta/"/>'),
+    (35, 'E1C1D1E1-0000-4000-8000-000000000035', 1, 0, 32, '<x:xmpmeta xmlns:x="adobe:ns:meta/"/>');
+
+INSERT INTO Adobe_imageProperties (id_local, id_global, image, propertiesString) VALUES
+    (36, 'E1C1D1E1-0000-4000-8000-000000000036', 30, 'properties = { cropAspectH = 2, cropAspectW = 3, }'),
+    (37, 'E1C1D1E1-0000-4000-8000-000000000037', 31, 'properties = { loupeFocusPoint = { _ag_className = "AgPoint", x = 0.5, y = 0.5, }, }');
+
+INSERT INTO AgLibraryCollection (id_local, creationId, genealogy, name, parent, systemOnly) VALUES
+    (40, 'com.adobe.ag.library.collection', '/240', 'Quick Collection', NULL, 1),
+    (41, 'com.adobe.ag.library.group', '/241', 'Trips', NULL, 0),
+    (42, 'com.adobe.ag.library.collection', '/241/242', 'Montréal', 41, 0),
+    (43, 'com.adobe.ag.library.smart_collection', '/243', 'Rated', NULL, 0);
+
+INSERT INTO AgLibraryCollectionImage (id_local, collection, image, pick, positionInCollection) VALUES
+    (44, 42, 30, 0, 'z'),
+    (45, 42, 31, 0, 'z');
+
+INSERT INTO AgLibraryCollectionContent (id_local, collection, content, owningModule) VALUES
+    (46, 42, 'captureTime', 'com.adobe.ag.library.sortType'),
+    (47, 42, 'ascending', 'com.adobe.ag.library.sortDirection'),
+    (48, 43, 's = { { criteria = "rating", operation = ">", value = 0, }, combine = "intersect", }', 'ag.library.smart_collection');
+
+INSERT INTO AgLibraryImport (id_local, imageCount, importDate, name) VALUES
+    (50, 2, '2017-10-03T09:00:00', NULL),
+    (51, 2, '2017-10-04T18:30:00', 'Trip');
+
+INSERT INTO AgLibraryImportImage (id_local, image, import) VALUES
+    (52, 30, 50),
+    (53, 31, 50),
+    (54, 32, 51),
+    (55, 33, 51);
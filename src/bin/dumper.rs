@@ -7,13 +7,15 @@
 extern crate lrcat;
 
 use std::collections::BTreeMap;
-use std::iter::FromIterator;
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
 use clap::{Parser, Subcommand};
 
 use lrcat::{
-    Catalog, Collection, Folders, Image, Keyword, KeywordTree, LibraryFile, LrId, LrObject,
+    Catalog, Collection, DuplicateFinder, DuplicateKey, FileStatus, Folder, FolderTree, Folders,
+    Image, Import, Keyword, KeywordTree, LibraryFile, LoadMode, LocatedPath, LrId, LrObject,
+    PathRemap, PathStrategy, Reconciler, RemapRule,
 };
 
 #[derive(Debug, Parser)]
@@ -29,8 +31,12 @@ enum Command {
     List(ListArgs),
     /// Dump the catalog.
     Dump(CommandArgs),
-    /// Audit (unimplemented).
-    Audit(CommandArgs),
+    /// Compare the catalog folders with the files on disk.
+    Audit(AuditArgs),
+    /// Convert lron to JSON.
+    Lron(LronArgs),
+    /// List the files catalogued several times.
+    Duplicates(DuplicatesArgs),
 }
 
 #[derive(Debug, Parser)]
@@ -58,6 +64,15 @@ struct CommandArgs {
     /// Dump keywords.
     #[arg(long)]
     keywords: bool,
+    /// Dump imports.
+    #[arg(long)]
+    imports: bool,
+    /// Fail on the first row that can't be loaded.
+    #[arg(long)]
+    strict: bool,
+    /// Work on a copy of the catalog, if Lightroom has it open.
+    #[arg(long)]
+    snapshot: bool,
 }
 
 #[derive(Debug, Parser)]
@@ -70,6 +85,95 @@ struct ListArgs {
     /// Only list directories
     #[arg(short)]
     dirs: bool,
+    /// Show the folder tree, with the image counts.
+    #[arg(long, conflicts_with_all = ["dirs", "sort"])]
+    tree: bool,
+    /// Work on a copy of the catalog, if Lightroom has it open.
+    #[arg(long)]
+    snapshot: bool,
+    #[command(flatten)]
+    remap: RemapArgs,
+}
+
+#[derive(Debug, clap::Args)]
+struct RemapArgs {
+    /// Rewrite the paths starting with FROM to start with TO.
+    #[arg(long, value_name = "FROM=TO")]
+    remap: Vec<String>,
+    /// Read remap rules from a file, one FROM=TO per line. Applied
+    /// after the --remap rules.
+    #[arg(long)]
+    remap_file: Option<PathBuf>,
+}
+
+impl RemapArgs {
+    fn path_remap(&self) -> lrcat::Result<PathRemap> {
+        let mut remap = PathRemap::new();
+        for rule in &self.remap {
+            remap.add_rule(RemapRule::parse(rule)?);
+        }
+        if let Some(ref file) = self.remap_file {
+            for rule in PathRemap::from_file(file)?.rules() {
+                remap.add_rule(rule.clone());
+            }
+        }
+        Ok(remap)
+    }
+}
+
+#[derive(Debug, Parser)]
+struct AuditArgs {
+    /// The catalog
+    path: PathBuf,
+    /// Also list the files that are present and unchanged.
+    #[arg(long)]
+    all: bool,
+    /// Work on a copy of the catalog, if Lightroom has it open.
+    #[arg(long)]
+    snapshot: bool,
+    #[command(flatten)]
+    remap: RemapArgs,
+}
+
+#[derive(Debug, Parser)]
+struct DuplicatesArgs {
+    /// The catalog
+    path: PathBuf,
+    /// Also compare the size of the files on disk.
+    #[arg(long)]
+    size: bool,
+    /// Also compare the content of the files on disk.
+    #[arg(long)]
+    content: bool,
+    /// Work on a copy of the catalog, if Lightroom has it open.
+    #[arg(long)]
+    snapshot: bool,
+    #[command(flatten)]
+    remap: RemapArgs,
+}
+
+#[derive(Debug, Parser)]
+struct LronArgs {
+    /// Files to convert. Read stdin if none or `-`.
+    files: Vec<PathBuf>,
+    /// Read the lron from the catalog instead of files.
+    #[arg(long, requires_all = ["table", "column", "id"], conflicts_with = "files")]
+    catalog: Option<PathBuf>,
+    /// Work on a copy of the catalog, if Lightroom has it open.
+    #[arg(long, requires = "catalog")]
+    snapshot: bool,
+    /// Table in the catalog.
+    #[arg(long)]
+    table: Option<String>,
+    /// Column in the table.
+    #[arg(long)]
+    column: Option<String>,
+    /// `id_local` of the row.
+    #[arg(long)]
+    id: Option<LrId>,
+    /// Decode nested lron strings.
+    #[arg(long)]
+    nested: bool,
 }
 
 fn main() -> lrcat::Result<()> {
@@ -78,35 +182,69 @@ fn main() -> lrcat::Result<()> {
     match args.command {
         Command::List(ref args) => process_list(args),
         Command::Dump(_) => process_dump(&args),
-        Command::Audit(_) => process_audit(&args),
+        Command::Audit(ref args) => process_audit(args),
+        Command::Lron(ref args) => process_lron(args),
+        Command::Duplicates(ref args) => process_duplicates(args),
     }
 }
 
-fn list_dirs(folders: &BTreeMap<LrId, String>, sort: bool) {
-    let mut folders = folders.values().collect::<Vec<&String>>();
+/// Open the catalog without modifying it.
+fn open_catalog(path: &Path, snapshot: bool) -> lrcat::Result<Catalog> {
+    let mut catalog = Catalog::new(path);
+    if snapshot {
+        catalog.open_snapshot()?;
+    } else if let Err(err) = catalog.open_read_only() {
+        if let lrcat::Error::CatalogLocked(_) = err {
+            eprintln!("The catalog is open in Lightroom. Use --snapshot to work on a copy.");
+        }
+        return Err(err);
+    }
+    Ok(catalog)
+}
+
+/// The directory of `folder` as listed: the root folder path followed
+/// by the path from the root, or the path relative to the catalog if
+/// only that one exists. Only the path from the root if the root is
+/// missing.
+fn folder_dir(catalog: &Catalog, folder: &Folder) -> String {
+    match catalog.locate_folder(folder) {
+        Some(LocatedPath {
+            path,
+            strategy: PathStrategy::RelativeToCatalog,
+        }) => format!("{}/", path.display()),
+        _ => catalog
+            .folders()
+            .resolve_folder_path(folder)
+            .unwrap_or_else(|| folder.path_from_root.clone()),
+    }
+}
+
+fn list_dirs(catalog: &Catalog, sort: bool) {
+    let mut dirs = catalog
+        .folders()
+        .folders()
+        .iter()
+        .map(|folder| folder_dir(catalog, folder))
+        .collect::<Vec<String>>();
     if sort {
-        folders.sort_unstable();
+        dirs.sort_unstable();
     }
-    folders.iter().for_each(|folder| println!("{folder}"));
+    dirs.iter().for_each(|dir| println!("{dir}"));
 }
 
-fn list_files(catalog: &mut Catalog, folders: &BTreeMap<LrId, String>, sort: bool) {
-    let libfiles = catalog.load_library_files();
-    let mut files = libfiles
+fn list_files(catalog: &Catalog, sort: bool) {
+    let mut files = catalog
+        .libfiles()
         .iter()
         .filter_map(|file| {
-            folders.get(&file.folder).map(|folder| {
-                let mut out = vec![format!("{folder}{}.{}", file.basename, file.extension)];
-                out.extend(file.sidecar_extensions.split(',').filter_map(|ext| {
-                    if !ext.is_empty() {
-                        Some(format!("{folder}{}.{ext}", file.basename))
-                    } else {
-                        None
-                    }
-                }));
-
-                out
-            })
+            let dir = folder_dir(catalog, catalog.folder(file.folder)?);
+            let mut out = vec![format!("{dir}{}.{}", file.basename, file.extension)];
+            out.extend(
+                file.sidecar_file_names()
+                    .iter()
+                    .map(|name| format!("{dir}{name}")),
+            );
+            Some(out)
         })
         .flatten()
         .collect::<Vec<String>>();
@@ -117,35 +255,42 @@ fn list_files(catalog: &mut Catalog, folders: &BTreeMap<LrId, String>, sort: boo
     files.iter().for_each(|file| println!("{file}"));
 }
 
-fn process_list(args: &ListArgs) -> lrcat::Result<()> {
-    let mut catalog = Catalog::new(&args.path);
-    catalog.open()?;
-    let folders = catalog.load_folders();
-
-    let roots = BTreeMap::from_iter(
-        folders
-            .roots
-            .iter()
-            .map(|folder| (folder.id(), folder.clone())),
-    );
-
-    let resolved_folders = BTreeMap::from_iter(folders.folders.iter().map(|folder| {
-        let root_path = if let Some(root) = roots.get(&folder.root_folder) {
-            &root.absolute_path
+fn list_tree(catalog: &Catalog, tree: &FolderTree) {
+    for (depth, id) in tree.iter() {
+        let name = if let Some(root) = catalog.root_folder(id) {
+            format!(
+                "{} ({})",
+                root.name,
+                catalog.folders().remap().remap(&root.absolute_path)
+            )
+        } else if let Some(folder) = catalog.folder(id) {
+            let path = folder.path_from_root.trim_end_matches('/');
+            path.rsplit('/').next().unwrap_or(path).to_string()
         } else {
-            ""
+            continue;
         };
+        println!(
+            "{:indent$}{name} [{}]",
+            "",
+            tree.total_image_count(id),
+            indent = depth * 4
+        );
+    }
+}
 
-        (
-            folder.id(),
-            format!("{}{}", root_path, &folder.path_from_root),
-        )
-    }));
-
-    if args.dirs {
-        list_dirs(&resolved_folders, args.sort);
+fn process_list(args: &ListArgs) -> lrcat::Result<()> {
+    let mut catalog = open_catalog(&args.path, args.snapshot)?;
+    catalog.set_path_remap(args.remap.path_remap()?);
+    catalog.load_folders()?;
+
+    if args.tree {
+        let tree = catalog.load_folder_tree()?;
+        list_tree(&catalog, &tree);
+    } else if args.dirs {
+        list_dirs(&catalog, args.sort);
     } else {
-        list_files(&mut catalog, &resolved_folders, args.sort);
+        catalog.load_library_files()?;
+        list_files(&catalog, args.sort);
     }
 
     Ok(())
@@ -153,10 +298,11 @@ fn process_list(args: &ListArgs) -> lrcat::Result<()> {
 
 fn process_dump(args: &Args) -> lrcat::Result<()> {
     if let Command::Dump(args) = &args.command {
-        let mut catalog = Catalog::new(&args.path);
-        catalog.open()?;
+        let mut catalog = open_catalog(&args.path, args.snapshot)?;
+        if args.strict {
+            catalog.set_load_mode(LoadMode::Strict);
+        }
 
-        catalog.load_version();
         println!("Catalog:");
         println!(
             "\tVersion: {} ({:?})",
@@ -164,15 +310,17 @@ fn process_dump(args: &Args) -> lrcat::Result<()> {
         );
         println!("\tRoot keyword id: {}", catalog.root_keyword_id);
 
-        if !catalog.catalog_version.is_supported() {
+        if let Err(err) = catalog.validate() {
             println!("Unsupported catalog version");
-            return Err(lrcat::Error::UnsupportedVersion);
+            return Err(err);
         }
 
+        let mut row_errors = vec![];
         {
             let root_keyword_id = catalog.root_keyword_id;
-            let keywordtree = catalog.load_keywords_tree();
-            let keywords = catalog.load_keywords();
+            row_errors.extend(catalog.load_keywords()?.errors);
+            let keywordtree = catalog.load_keywords_tree()?;
+            let keywords = catalog.keywords();
             println!("\tKeywords count: {}", keywords.len());
 
             if args.all || args.keywords {
@@ -181,32 +329,45 @@ fn process_dump(args: &Args) -> lrcat::Result<()> {
         }
 
         {
-            let folders = catalog.load_folders();
+            let folders = catalog.load_folders()?;
             if args.all || args.root {
-                dump_root_folders(folders);
+                dump_root_folders(&folders);
             }
             if args.all || args.folders {
-                dump_folders(folders);
+                dump_folders(&folders);
             }
+            row_errors.extend(folders.errors);
         }
 
         {
-            let libfiles = catalog.load_library_files();
+            let libfiles = catalog.load_library_files()?;
             if args.all || args.libfiles {
-                dump_libfiles(libfiles);
+                dump_libfiles(&libfiles);
             }
+            row_errors.extend(libfiles.errors);
         }
         {
-            let images = catalog.load_images();
+            let images = catalog.load_images()?;
             if args.all || args.images {
-                dump_images(images);
+                dump_images(&images);
             }
+            row_errors.extend(images.errors);
         }
         {
-            let collections = catalog.load_collections();
+            let collections = catalog.load_collections()?;
             if args.all || args.collections {
-                dump_collections(collections);
+                dump_collections(&collections);
             }
+            row_errors.extend(collections.errors);
+        }
+        if args.all || args.imports {
+            let imports = catalog.load_imports()?;
+            dump_imports(&imports);
+            row_errors.extend(imports.errors);
+        }
+
+        for error in row_errors {
+            eprintln!("Failed to load row: {error}");
         }
     }
     Ok(())
@@ -256,7 +417,7 @@ fn dump_root_folders(folders: &Folders) {
     println!("+---------+--------------------------------------+------------------+----------------------------");
     println!("| id      | uuid                                 | name             | absolute path");
     println!("+---------+--------------------------------------+------------------+----------------------------");
-    for root in &folders.roots {
+    for root in folders.roots() {
         println!(
             "| {:>7} | {} | {:<16} | {:<26}",
             root.id(),
@@ -276,7 +437,7 @@ fn dump_folders(folders: &Folders) {
         "| id      | uuid                                 | root   | path                        |"
     );
     println!("+---------+--------------------------------------+--------+-----------------------------+----------");
-    for folder in &folders.folders {
+    for folder in folders.folders() {
         println!(
             "| {:>7} | {} | {:>7} | {:<26} | {:?}",
             folder.id(),
@@ -291,21 +452,22 @@ fn dump_folders(folders: &Folders) {
 
 fn dump_libfiles(libfiles: &[LibraryFile]) {
     println!("Libfiles");
-    println!("+---------+--------------------------------------+---------+--------+---------------------+----------+");
-    println!("| id      | uuid                                 | folder  | extens | basename            | sidecars |");
-    println!("+---------+--------------------------------------+---------+--------+---------------------+----------+");
+    println!("+---------+--------------------------------------+---------+--------+---------------------+----------+---------------------+");
+    println!("| id      | uuid                                 | folder  | extens | basename            | sidecars | original            |");
+    println!("+---------+--------------------------------------+---------+--------+---------------------+----------+---------------------+");
     for libfile in libfiles {
         println!(
-            "| {:>7} | {} | {:>7} | {:<6} | {:<19} | {:<8} |",
+            "| {:>7} | {} | {:>7} | {:<6} | {:<19} | {:<8} | {:<19} |",
             libfile.id(),
             libfile.uuid(),
             libfile.folder,
             libfile.extension,
             libfile.basename,
-            libfile.sidecar_extensions
+            libfile.sidecar_extensions,
+            libfile.original_filename
         );
     }
-    println!("+---------+--------------------------------------+---------+--------+---------------------+----------+");
+    println!("+---------+--------------------------------------+---------+--------+---------------------+----------+---------------------+");
 }
 
 fn dump_images(images: &[Image]) {
@@ -325,7 +487,7 @@ fn dump_images(images: &[Image]) {
             image.orientation.as_ref().unwrap_or(&String::new()),
             image.exif_orientation(),
             image.pick,
-            image.xmp.len(),
+            image.xmp.as_ref().map_or(0, String::len),
         );
     }
     println!("+---------+--------------------------------------+---------+--------+-------+----+-----------");
@@ -349,6 +511,193 @@ fn dump_collections(collections: &[Collection]) {
     println!("+---------+--------------------------------------+---------+-------+----------------------");
 }
 
-fn process_audit(_: &Args) -> lrcat::Result<()> {
-    Err(lrcat::Error::Unimplemented)
+fn dump_imports(imports: &[Import]) {
+    println!("Imports");
+    println!("+---------+---------------------+--------------------------------------+--------+----------------------");
+    println!(
+        "| id      | date                | name                                 | count  | images"
+    );
+    println!("+---------+---------------------+--------------------------------------+--------+----------------------");
+    for import in imports {
+        println!(
+            "| {:>7} | {:<19} | {:<36} | {:>6} | {:?}",
+            import.id(),
+            import.date,
+            import.name.as_deref().unwrap_or_default(),
+            import.images.len(),
+            import.images
+        )
+    }
+    println!("+---------+---------------------+--------------------------------------+--------+----------------------");
+}
+
+fn process_duplicates(args: &DuplicatesArgs) -> lrcat::Result<()> {
+    let mut catalog = open_catalog(&args.path, args.snapshot)?;
+    catalog.set_path_remap(args.remap.path_remap()?);
+    catalog.load_folders()?;
+    catalog.load_library_files()?;
+    catalog.load_images()?;
+
+    let groups = DuplicateFinder::new(&catalog)
+        .file_size(args.size)
+        .content_hash(args.content)
+        .find();
+    for group in &groups {
+        match group.key {
+            DuplicateKey::Md5(ref md5) => println!("Same MD5 {md5}:"),
+            DuplicateKey::ImportHash(ref hash) => println!("Same import hash {hash}:"),
+            DuplicateKey::NameTimeSize {
+                ref original_filename,
+                ref capture_time,
+                size,
+            } => println!(
+                "Same name {original_filename}, captured {capture_time}, size {}:",
+                size.map(|size| size.to_string())
+                    .unwrap_or_else(|| "not compared".to_string())
+            ),
+            DuplicateKey::ContentHash(ref hash) => println!("Same content {hash}:"),
+        }
+        for id in &group.files {
+            let path = catalog
+                .library_file(*id)
+                .and_then(|file| catalog.library_file_path(file))
+                .map(|path| path.display().to_string())
+                .unwrap_or_default();
+            let best = if *id == group.best { "*" } else { " " };
+            println!("  {best} {id:>7} {path}");
+        }
+    }
+    println!("{} group(s) of duplicates.", groups.len());
+
+    Ok(())
+}
+
+fn process_audit(args: &AuditArgs) -> lrcat::Result<()> {
+    let mut catalog = open_catalog(&args.path, args.snapshot)?;
+    catalog.set_path_remap(args.remap.path_remap()?);
+    catalog.load_folders()?;
+    catalog.load_library_files()?;
+
+    let report = Reconciler::new(&catalog).reconcile();
+    for id in &report.missing_folders {
+        if let Some(path) = catalog
+            .folder(*id)
+            .and_then(|folder| catalog.folders().folder_path(folder))
+        {
+            println!("Missing folder: {}", path.display());
+        }
+    }
+    for entry in &report.entries {
+        let status = match entry.status {
+            FileStatus::Present if !args.all => continue,
+            FileStatus::Present => "present",
+            FileStatus::Missing => "missing",
+            FileStatus::New => "new",
+            FileStatus::Modified => "modified",
+            FileStatus::SidecarMismatch => "sidecars",
+        };
+        println!("{status:<8} {}", entry.path.display());
+        for sidecar in &entry.missing_sidecars {
+            println!("         - {}", sidecar.display());
+        }
+        for sidecar in &entry.unexpected_sidecars {
+            println!("         + {}", sidecar.display());
+        }
+    }
+    for (status, count) in report.counts() {
+        println!("{status:?}: {count}");
+    }
+
+    Ok(())
+}
+
+/// Print the parse error with the line of `text` it happened at.
+fn print_lron_error(name: &str, text: &str, err: &peg::error::ParseError<peg::str::LineCol>) {
+    let location = &err.location;
+    eprintln!(
+        "{name}:{}:{}: error: expected {}",
+        location.line, location.column, err.expected
+    );
+    if let Some(line) = text.lines().nth(location.line - 1) {
+        let number = location.line.to_string();
+        let margin = " ".repeat(number.len());
+        eprintln!("{margin} |");
+        eprintln!("{number} | {line}");
+        eprintln!("{margin} | {}^", " ".repeat(location.column - 1));
+    }
+}
+
+fn process_lron(args: &LronArgs) -> lrcat::Result<()> {
+    let mut failed = false;
+    let mut sources = vec![];
+    if let Some(ref path) = args.catalog {
+        let catalog = open_catalog(path, args.snapshot)?;
+        let table = args.table.as_deref().unwrap_or_default();
+        let column = args.column.as_deref().unwrap_or_default();
+        let id = args.id.unwrap_or_default();
+        match catalog.get_text_value(table, column, id)? {
+            Some(text) => sources.push((format!("{table}.{column}[{id}]"), text)),
+            None => {
+                eprintln!("{table}.{column}[{id}]: no value");
+                failed = true;
+            }
+        }
+    } else {
+        let stdin = PathBuf::from("-");
+        let files = if args.files.is_empty() {
+            std::slice::from_ref(&stdin)
+        } else {
+            &args.files[..]
+        };
+        // In the order given. stdin can only be read once.
+        let mut stdin_read = false;
+        for file in files {
+            let (name, result) = if file.as_os_str() == "-" {
+                if stdin_read {
+                    eprintln!("<stdin>: already read");
+                    failed = true;
+                    continue;
+                }
+                stdin_read = true;
+                let mut text = String::new();
+                let result = std::io::stdin().read_to_string(&mut text).map(|_| text);
+                ("<stdin>".to_owned(), result)
+            } else {
+                (
+                    file.to_string_lossy().into_owned(),
+                    std::fs::read_to_string(file),
+                )
+            };
+            match result {
+                Ok(text) => sources.push((name, text)),
+                Err(err) => {
+                    eprintln!("{name}: {err}");
+                    failed = true;
+                }
+            }
+        }
+    }
+
+    for (name, text) in &sources {
+        let result = if args.nested {
+            lrcat::lron::Value::from_string_nested(text)
+        } else {
+            lrcat::lron::Value::from_string(text)
+        };
+        match result {
+            Ok(value) => println!(
+                "{}",
+                serde_json::to_string_pretty(&value.to_json()).unwrap_or_default()
+            ),
+            Err(err) => {
+                print_lron_error(name, text, &err);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
 }
@@ -4,19 +4,30 @@
  file, You can obtain one at http://mozilla.org/MPL/2.0/.
 */
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use rusqlite::{params, Connection};
+#[cfg(feature = "snapshot")]
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::{params, Connection, ErrorCode, OpenFlags, OptionalExtension, MAIN_DB};
 
 use crate::collections::Collection;
-use crate::folders::{Folder, Folders, RootFolder};
-use crate::fromdb::FromDb;
+use crate::content::Content;
+use crate::folders::{Folder, Folders, LocatedPath, RootFolder};
+use crate::foldertree::FolderTree;
+use crate::fromdb::{select_query, FromDb, LoadMode, Loaded, ObjectIter, RowError};
+use crate::imageref::{FileRef, ImageRef};
 use crate::images::Image;
+use crate::imports::Import;
 use crate::keywords::Keyword;
 use crate::keywordtree::KeywordTree;
 use crate::libraryfiles::LibraryFile;
-use crate::lrobject::{LrId, LrObject};
+use crate::lrobject::{CatalogObject, LrId, LrObject, ObjectIndex};
+use crate::relocate::{self, RootRelocation};
+use crate::remap::PathRemap;
+use crate::shared::{ConnectionPool, SharedCatalog};
 
 const LR2_VERSION: i32 = 2;
 const LR3_VERSION: i32 = 3;
@@ -45,6 +56,63 @@ impl CatalogVersion {
     }
 }
 
+/// A step of `Catalog::load_all()`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LoadStep {
+    /// The keywords.
+    Keywords,
+    /// The root folders and the folders.
+    Folders,
+    /// The library files.
+    LibraryFiles,
+    /// The images.
+    Images,
+    /// The collections.
+    Collections,
+}
+
+/// Progress reported by `Catalog::load_all()` each time a step is
+/// done. Steps run concurrently and complete in any order.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LoadProgress {
+    /// The step that is done.
+    pub step: LoadStep,
+    /// The number of objects loaded by the step.
+    pub count: usize,
+    /// The number of steps done so far, including this one.
+    pub completed: usize,
+    /// The number of steps to do.
+    pub total: usize,
+}
+
+/// The objects loaded by a `LoadStep`, with the row errors.
+enum StepObjects {
+    Keywords(Vec<Keyword>, Vec<RowError>),
+    Folders(Vec<RootFolder>, Vec<Folder>, Vec<RowError>),
+    LibraryFiles(Vec<LibraryFile>, Vec<RowError>),
+    Images(Vec<Image>, Vec<RowError>),
+    Collections(Vec<Collection>, Vec<RowError>),
+}
+
+impl StepObjects {
+    fn count(&self) -> usize {
+        match self {
+            StepObjects::Keywords(objects, _) => objects.len(),
+            StepObjects::Folders(roots, folders, _) => roots.len() + folders.len(),
+            StepObjects::LibraryFiles(objects, _) => objects.len(),
+            StepObjects::Images(objects, _) => objects.len(),
+            StepObjects::Collections(objects, _) => objects.len(),
+        }
+    }
+}
+
+/// The temporary directory holding a snapshot of the catalog.
+#[cfg(feature = "snapshot")]
+pub(crate) type SnapshotDir = tempfile::TempDir;
+/// Without the `snapshot` feature there is never a snapshot.
+#[cfg(not(feature = "snapshot"))]
+pub(crate) type SnapshotDir = ();
+
 /// Catalog is the main container for Lightroom. It represents
 /// the .lrcat database.
 pub struct Catalog {
@@ -63,13 +131,28 @@ pub struct Catalog {
     folders: Folders,
     /// The `Image` container
     images: Vec<Image>,
+    /// Index of `images`
+    image_index: ObjectIndex,
     /// The `LibraryFile` container
     libfiles: Vec<LibraryFile>,
+    /// Index of `libfiles`
+    libfile_index: ObjectIndex,
+    /// The keywords id, mapped by uuid.
+    keyword_uuids: HashMap<String, LrId>,
     /// The `Collection` container
     collections: Vec<Collection>,
+    /// The `Import` container
+    imports: Vec<Import>,
+
+    /// How to handle rows that fail to load.
+    load_mode: LoadMode,
 
     /// The sqlite connectio to the catalog
     dbconn: Option<Connection>,
+    /// The temporary directory holding the snapshot of the catalog,
+    /// if opened with `open_snapshot()`. After `dbconn` to be dropped
+    /// last.
+    snapshot: Option<SnapshotDir>,
 }
 
 impl Catalog {
@@ -86,35 +169,321 @@ impl Catalog {
             keywords: BTreeMap::new(),
             folders: Folders::new(),
             images: vec![],
+            image_index: ObjectIndex::default(),
             libfiles: vec![],
+            libfile_index: ObjectIndex::default(),
+            keyword_uuids: HashMap::new(),
             collections: vec![],
+            imports: vec![],
+            load_mode: LoadMode::default(),
             dbconn: None,
+            snapshot: None,
         }
     }
 
-    /// Open catalog. Return false in failure.
-    /// This doesn't check if the content is valid beyond the backing sqlite3.
+    /// Open catalog, check it is a Lightroom catalog and load the
+    /// version. The catalog is opened read-write: to not touch a user
+    /// catalog, use `open_read_only()` or `open_snapshot()`.
     pub fn open(&mut self) -> crate::Result<()> {
-        let conn = Connection::open(&self.path)?;
+        Catalog::check_header(&self.path)?;
+        let conn = Connection::open_with_flags(
+            &self.path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        // Lightroom keeps the catalog locked while it is open, no
+        // point in waiting.
+        conn.busy_timeout(std::time::Duration::ZERO)?;
+
+        self.set_connection(conn)
+    }
+
+    /// The header of SQLite 3 files.
+    const SQLITE_HEADER: &'static [u8; 16] = b"SQLite format 3\0";
+
+    /// Check that the file at `path` is a SQLite 3 database. `:memory:`
+    /// and `file:` URIs aren't files and are left to sqlite.
+    fn check_header(path: &Path) -> crate::Result<()> {
+        let name = path.to_string_lossy();
+        if name == ":memory:" || name.starts_with("file:") {
+            return Ok(());
+        }
+        let mut header = [0_u8; 16];
+        let mut file = std::fs::File::open(path)?;
+        match file.read_exact(&mut header) {
+            Ok(_) if &header == Catalog::SQLITE_HEADER => Ok(()),
+            Ok(_) => Err(crate::Error::NotSqlite),
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                Err(crate::Error::NotSqlite)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
 
+    /// Use `conn` for the catalog after checking it is a Lightroom
+    /// catalog, and load the version.
+    fn set_connection(&mut self, conn: Connection) -> crate::Result<()> {
         self.dbconn = Some(conn);
+        let result = self.check_catalog();
+        if result.is_err() {
+            self.dbconn = None;
+            self.snapshot = None;
+        }
+        result
+    }
 
-        Ok(())
+    /// Check the database is a Lightroom catalog and load the version.
+    fn check_catalog(&mut self) -> crate::Result<()> {
+        let conn = self.dbconn.as_ref().ok_or(crate::Error::NotOpen)?;
+        let has_variables = conn
+            .query_row(
+                "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = 'Adobe_variablesTable'",
+                [],
+                |row| row.get::<usize, i64>(0),
+            )
+            .map_err(|err| self.open_error(err))?
+            > 0;
+        if !has_variables {
+            return Err(crate::Error::NotLightroomCatalog);
+        }
+        self.load_version()
+    }
+
+    /// Convert the sqlite error `err` from reading the catalog for the
+    /// first time.
+    fn open_error(&self, err: rusqlite::Error) -> crate::Error {
+        match err.sqlite_error_code() {
+            Some(ErrorCode::NotADatabase) | Some(ErrorCode::DatabaseCorrupt) => {
+                crate::Error::Corrupt(err)
+            }
+            Some(ErrorCode::DatabaseBusy) | Some(ErrorCode::DatabaseLocked) => self.locked_error(),
+            _ => err.into(),
+        }
+    }
+
+    /// The `Error::CatalogLocked` for the catalog, with the lock file
+    /// if any.
+    fn locked_error(&self) -> crate::Error {
+        crate::Error::CatalogLocked(
+            self.lock_files()
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| self.path.clone()),
+        )
+    }
+
+    /// Catalog path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The directory containing the catalog, that root folder paths
+    /// relative to the catalog start from.
+    pub fn directory(&self) -> PathBuf {
+        match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        }
+    }
+
+    /// The suffixes of the files that exist next to the catalog while
+    /// Lightroom has it open.
+    /// Lightroom creates `<name>.lrcat-lock`, and sqlite
+    /// `<name>.lrcat-wal`.
+    const LOCK_SUFFIXES: [&'static str; 2] = ["-lock", "-wal"];
+
+    /// Return the files next to the catalog indicating that Lightroom
+    /// has it open. The `-wal` file contains changes not yet written
+    /// to the catalog.
+    pub fn lock_files(&self) -> Vec<PathBuf> {
+        Self::LOCK_SUFFIXES
+            .iter()
+            .map(|suffix| {
+                let mut path = self.path.clone().into_os_string();
+                path.push(suffix);
+                PathBuf::from(path)
+            })
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    /// Open the catalog read-only, without creating or locking any
+    /// file, so that it is safe to use on a user catalog. Lightroom
+    /// must not have the catalog open, otherwise it return
+    /// `Error::CatalogLocked`: use `open_snapshot()` instead.
+    pub fn open_read_only(&mut self) -> crate::Result<()> {
+        if let Some(lock) = self.lock_files().into_iter().next() {
+            return Err(crate::Error::CatalogLocked(lock));
+        }
+
+        Catalog::check_header(&self.path)?;
+        let conn = Catalog::open_connection(&self.path, true)?;
+
+        self.set_connection(conn)
+    }
+
+    /// Open a read-only connection on the catalog at `path`. With
+    /// `immutable` sqlite is told that nobody will change the file: no
+    /// lock nor journal.
+    pub(crate) fn open_connection(path: &Path, immutable: bool) -> crate::Result<Connection> {
+        let mut uri = Catalog::path_uri(path);
+        uri += if immutable {
+            "?immutable=1"
+        } else {
+            "?mode=ro"
+        };
+        Ok(Connection::open_with_flags(
+            uri,
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?)
+    }
+
+    /// The `file:` URI of `path`, for the connections opened with
+    /// `SQLITE_OPEN_URI`. Every byte but the unreserved characters and
+    /// `/` is percent-encoded.
+    pub(crate) fn path_uri(path: &Path) -> String {
+        #[cfg(unix)]
+        let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+        #[cfg(not(unix))]
+        let bytes = path.to_string_lossy().into_owned().into_bytes();
+        let mut uri = String::from("file:");
+        for byte in bytes {
+            match byte {
+                #[cfg(windows)]
+                b'\\' => uri.push('/'),
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                    uri.push(byte as char)
+                }
+                _ => uri += &format!("%{byte:02X}"),
+            }
+        }
+        uri
+    }
+
+    /// Copy the catalog, and its pending changes, into a temporary
+    /// directory and open the copy. This works even if Lightroom has
+    /// the catalog open. The copy is removed when the `Catalog` is
+    /// dropped.
+    ///
+    /// Requires the `snapshot` feature.
+    #[cfg(feature = "snapshot")]
+    pub fn open_snapshot(&mut self) -> crate::Result<()> {
+        Catalog::check_header(&self.path)?;
+        let dir = tempfile::tempdir()?;
+        let copy = dir.path().join(self.snapshot_file_name());
+        let source = Catalog::open_connection(&self.path, false).map_err(|err| match err {
+            crate::Error::Sql(err) => self.open_error(err),
+            _ => err,
+        })?;
+        let mut conn = Connection::open(&copy)?;
+        {
+            // All the pages in one step, so in one read transaction:
+            // the copy is consistent, with the changes still in the
+            // WAL.
+            let backup = Backup::new(&source, &mut conn)?;
+            match backup.step(-1).map_err(|err| self.open_error(err))? {
+                StepResult::Done => {}
+                _ => return Err(self.locked_error()),
+            }
+        }
+
+        self.snapshot = Some(dir);
+        self.set_connection(conn)
+    }
+
+    /// The file name of the catalog copy in the snapshot directory.
+    #[cfg(feature = "snapshot")]
+    fn snapshot_file_name(&self) -> &std::ffi::OsStr {
+        self.path
+            .file_name()
+            .unwrap_or_else(|| "catalog.lrcat".as_ref())
+    }
+
+    /// The path of the catalog copy, if opened with `open_snapshot()`.
+    #[cfg(feature = "snapshot")]
+    fn snapshot_path(&self) -> Option<PathBuf> {
+        self.snapshot
+            .as_ref()
+            .map(|dir| dir.path().join(self.snapshot_file_name()))
+    }
+
+    #[cfg(not(feature = "snapshot"))]
+    fn snapshot_path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Turn the catalog, with the objects loaded so far, into a
+    /// `SharedCatalog` that can be used from several threads. The
+    /// connection is closed and the `SharedCatalog` opens read-only
+    /// connections as needed.
+    pub fn into_shared(mut self) -> crate::Result<SharedCatalog> {
+        let conn = self.dbconn.take().ok_or(crate::Error::NotOpen)?;
+        let snapshot = self.snapshot_path();
+        let immutable = snapshot.is_some() || conn.is_readonly(MAIN_DB)?;
+        // Closing the connection of a snapshot write the WAL into the
+        // copy, that can then be opened immutable.
+        conn.close().map_err(|(_, err)| err)?;
+        let path = snapshot.unwrap_or_else(|| self.path.clone());
+        let pool = ConnectionPool::new(path, immutable, self.snapshot.take());
+
+        Ok(SharedCatalog {
+            path: self.path,
+            version: self.version,
+            catalog_version: self.catalog_version,
+            root_keyword_id: self.root_keyword_id,
+            keywords: Arc::new(self.keywords),
+            keyword_uuids: Arc::new(self.keyword_uuids),
+            folders: Arc::new(self.folders),
+            images: Arc::new(self.images),
+            image_index: Arc::new(self.image_index),
+            libfiles: Arc::new(self.libfiles),
+            libfile_index: Arc::new(self.libfile_index),
+            collections: Arc::new(self.collections),
+            row_errors: Arc::new(vec![]),
+            pool: Arc::new(pool),
+        })
     }
 
     /// Get a variable from the table.
-    fn get_variable<T>(&self, name: &str) -> Option<T>
+    fn get_variable<T>(&self, name: &str) -> crate::Result<Option<T>>
     where
         T: rusqlite::types::FromSql,
     {
-        let conn = self.dbconn.as_ref()?;
-        if let Ok(mut stmt) = conn.prepare("SELECT value FROM Adobe_variablesTable WHERE name=?1") {
-            let mut rows = stmt.query([&name]).unwrap();
-            if let Ok(Some(row)) = rows.next() {
-                return row.get(0).ok();
-            }
-        }
-        None
+        let conn = self.dbconn.as_ref().ok_or(crate::Error::NotOpen)?;
+        let value = conn
+            .query_row(
+                "SELECT value FROM Adobe_variablesTable WHERE name=?1",
+                [&name],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|err| self.open_error(err))?;
+        Ok(value)
+    }
+
+    /// Get the raw text value of `column` in `table` for the row whose
+    /// `id_local` is `id`. Return `None` if there is no such row or the
+    /// value is NULL. This is meant to access data that isn't otherwise
+    /// exposed, like the lron columns.
+    pub fn get_text_value(
+        &self,
+        table: &str,
+        column: &str,
+        id: LrId,
+    ) -> crate::Result<Option<String>> {
+        let conn = self.dbconn.as_ref().ok_or(crate::Error::NotOpen)?;
+        let query = format!(
+            "SELECT \"{}\" FROM \"{}\" WHERE id_local = ?1",
+            column.replace('"', "\"\""),
+            table.replace('"', "\"\"")
+        );
+        let value = conn
+            .query_row(&query, [&id], |row| row.get::<usize, Option<String>>(0))
+            .optional()?;
+        Ok(value.flatten())
     }
 
     /// Parse the version string from the database.
@@ -123,67 +492,174 @@ impl Catalog {
         v.parse::<i32>().unwrap_or_default()
     }
 
-    /// Load version info for the catalog.
-    pub fn load_version(&mut self) {
-        if let Some(version) = self.get_variable::<String>("Adobe_DBVersion") {
-            self.version = version;
-            let v = Catalog::parse_version(self.version.clone());
-            self.catalog_version = match v {
-                LR6_VERSION => CatalogVersion::Lr6,
-                LR4_VERSION => CatalogVersion::Lr4,
-                LR3_VERSION => CatalogVersion::Lr3,
-                LR2_VERSION => CatalogVersion::Lr2,
-                _ => CatalogVersion::Unknown,
-            };
-        }
+    /// Load version info for the catalog. This is done when opening.
+    /// Return `Error::NotLightroomCatalog` if there is no version. An
+    /// unsupported version isn't an error, see `validate()`.
+    pub fn load_version(&mut self) -> crate::Result<()> {
+        let version = self
+            .get_variable::<String>("Adobe_DBVersion")?
+            .ok_or(crate::Error::NotLightroomCatalog)?;
+        self.version = version;
+        let v = Catalog::parse_version(self.version.clone());
+        self.catalog_version = match v {
+            LR6_VERSION => CatalogVersion::Lr6,
+            LR4_VERSION => CatalogVersion::Lr4,
+            LR3_VERSION => CatalogVersion::Lr3,
+            LR2_VERSION => CatalogVersion::Lr2,
+            _ => CatalogVersion::Unknown,
+        };
 
-        if let Some(root_keyword_id) = self.get_variable::<f64>("AgLibraryKeyword_rootTagID") {
+        if let Ok(Some(root_keyword_id)) = self.get_variable::<f64>("AgLibraryKeyword_rootTagID") {
             self.root_keyword_id = root_keyword_id.round() as LrId;
         }
+
+        Ok(())
     }
 
-    /// Generic object loader leveraging the FromDb protocol
-    fn load_objects<T: FromDb>(conn: &Connection, catalog_version: CatalogVersion) -> Vec<T> {
-        let mut query = format!(
-            "SELECT {} FROM {}",
-            T::read_db_columns(catalog_version),
-            T::read_db_tables(catalog_version)
-        );
-        let where_join = T::read_join_where(catalog_version);
-        if !where_join.is_empty() {
-            query += &format!(" WHERE {where_join}");
-        }
-        if let Ok(mut stmt) = conn.prepare(&query) {
-            if let Ok(rows) =
-                stmt.query_and_then(params![], |row| T::read_from(catalog_version, row))
-            {
-                return rows.into_iter().filter_map(|obj| obj.ok()).collect();
+    /// Check that the catalog version is supported. Return
+    /// `Error::VersionTooNew` with the version string if the catalog
+    /// is from a newer Lightroom, like Lightroom Classic, and
+    /// `Error::UnsupportedVersion` for the other unsupported versions.
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.catalog_version.is_supported() {
+            Ok(())
+        } else if Catalog::parse_version(self.version.clone()) > LR6_VERSION {
+            Err(crate::Error::VersionTooNew(self.version.clone()))
+        } else {
+            Err(crate::Error::UnsupportedVersion)
+        }
+    }
+
+    /// Set how to handle rows that fail to load. Default is
+    /// `LoadMode::Lenient`.
+    pub fn set_load_mode(&mut self, mode: LoadMode) {
+        self.load_mode = mode;
+    }
+
+    /// How rows that fail to load are handled.
+    pub fn load_mode(&self) -> LoadMode {
+        self.load_mode
+    }
+
+    /// Generic object loader leveraging the FromDb protocol.
+    /// In `LoadMode::Strict` the first row error is returned,
+    /// otherwise the row errors are returned with the objects. Row
+    /// warnings are row errors, but the object is kept in
+    /// `LoadMode::Lenient`.
+    fn load_objects<T: FromDb>(
+        conn: &Connection,
+        catalog_version: CatalogVersion,
+        mode: LoadMode,
+    ) -> crate::Result<(Vec<T>, Vec<RowError>)> {
+        let table = T::read_db_tables(catalog_version);
+        let query = select_query::<T>(catalog_version, "");
+        let mut stmt = conn.prepare(&query)?;
+        let mut rows = stmt.query(params![])?;
+        let mut objects = vec![];
+        let mut errors = vec![];
+        while let Some(row) = rows.next()? {
+            match T::read_from_with_warnings(catalog_version, row) {
+                Ok((object, warnings)) => {
+                    for (idx, warning) in warnings {
+                        let error = RowError::warning(table, row, idx, warning);
+                        if mode == LoadMode::Strict {
+                            return Err(crate::Error::Row(error));
+                        }
+                        errors.push(error);
+                    }
+                    objects.push(object);
+                }
+                Err(crate::Error::Skip) => {}
+                Err(err) => {
+                    let error = RowError::new(table, row, &err);
+                    if mode == LoadMode::Strict {
+                        return Err(crate::Error::Row(error));
+                    }
+                    errors.push(error);
+                }
             }
         }
-        vec![]
+        Ok((objects, errors))
+    }
+
+    /// Load the objects of type `T` with the catalog `LoadMode`.
+    fn load<T: FromDb>(&self) -> crate::Result<(Vec<T>, Vec<RowError>)> {
+        let conn = self.dbconn.as_ref().ok_or(crate::Error::NotOpen)?;
+        Catalog::load_objects::<T>(conn, self.catalog_version, self.load_mode)
+    }
+
+    /// Iterate over the objects of type `T`.
+    fn iter<T: FromDb>(&self) -> crate::Result<ObjectIter<'_, T>> {
+        let conn = self.dbconn.as_ref().ok_or(crate::Error::NotOpen)?;
+        Ok(ObjectIter::new(conn, self.catalog_version))
+    }
+
+    /// Iterate over the keywords, without caching them.
+    pub fn iter_keywords(&self) -> crate::Result<ObjectIter<'_, Keyword>> {
+        self.iter()
+    }
+
+    /// Iterate over the root folders, without caching them.
+    pub fn iter_root_folders(&self) -> crate::Result<ObjectIter<'_, RootFolder>> {
+        self.iter()
+    }
+
+    /// Iterate over the folders, without caching them. `content` isn't
+    /// loaded: use `Folder::read_content()`.
+    pub fn iter_folders(&self) -> crate::Result<ObjectIter<'_, Folder>> {
+        self.iter()
+    }
+
+    /// Iterate over the library files, without caching them.
+    pub fn iter_library_files(&self) -> crate::Result<ObjectIter<'_, LibraryFile>> {
+        self.iter()
     }
 
-    /// Load a keyword tree
-    pub fn load_keywords_tree(&mut self) -> KeywordTree {
-        let keywords = self.load_keywords();
+    /// Iterate over the images, without caching them.
+    pub fn iter_images(&self) -> crate::Result<ObjectIter<'_, Image>> {
+        self.iter()
+    }
+
+    /// Iterate over the collections, without caching them. `content`
+    /// isn't loaded: use `Collection::read_content()`.
+    pub fn iter_collections(&self) -> crate::Result<ObjectIter<'_, Collection>> {
+        self.iter()
+    }
+
+    /// Iterate over the imports, without caching them. `images` isn't
+    /// loaded: use `images_for_import()`.
+    pub fn iter_imports(&self) -> crate::Result<ObjectIter<'_, Import>> {
+        self.iter()
+    }
+
+    /// Load a keyword tree. Use `load_keywords()` first to get the rows
+    /// that failed to load.
+    pub fn load_keywords_tree(&mut self) -> crate::Result<KeywordTree> {
+        let keywords = self.load_keywords()?;
 
         let mut tree = KeywordTree::new();
-        tree.add_children(keywords);
+        tree.add_children(&keywords);
 
-        tree
+        Ok(tree)
     }
 
     /// Load keywords.
-    pub fn load_keywords(&mut self) -> &BTreeMap<LrId, Keyword> {
+    pub fn load_keywords(&mut self) -> crate::Result<Loaded<'_, BTreeMap<LrId, Keyword>>> {
+        let mut errors = vec![];
         if self.keywords.is_empty() {
-            if let Some(ref conn) = self.dbconn {
-                let result = Catalog::load_objects::<Keyword>(conn, self.catalog_version);
-                for keyword in result {
-                    self.keywords.insert(keyword.id(), keyword);
-                }
-            }
+            let (result, mut row_errors) = self.load::<Keyword>()?;
+            errors.append(&mut row_errors);
+            self.add_keywords(result);
+        }
+        Ok(Loaded::new(&self.keywords, errors))
+    }
+
+    fn add_keywords(&mut self, keywords: Vec<Keyword>) {
+        for keyword in keywords {
+            self.keyword_uuids
+                .insert(keyword.uuid().to_owned(), keyword.id());
+            self.keywords.insert(keyword.id(), keyword);
         }
-        &self.keywords
     }
 
     /// Get the keywords. This assume the keywords have been loaded first.
@@ -193,19 +669,73 @@ impl Catalog {
     }
 
     /// Load folders.
-    pub fn load_folders(&mut self) -> &Folders {
+    pub fn load_folders(&mut self) -> crate::Result<Loaded<'_, Folders>> {
+        let mut errors = vec![];
         if self.folders.is_empty() {
+            let (roots, mut row_errors) = self.load::<RootFolder>()?;
+            errors.append(&mut row_errors);
+            let (mut folders, mut row_errors) = self.load::<Folder>()?;
+            errors.append(&mut row_errors);
             if let Some(ref conn) = self.dbconn {
-                let folders = Catalog::load_objects::<RootFolder>(conn, self.catalog_version);
-                self.folders.append_root_folders(folders);
-                let mut folders = Catalog::load_objects::<Folder>(conn, self.catalog_version);
-                for folder in &mut folders {
-                    folder.content = Some(folder.read_content(conn));
-                }
-                self.folders.append_folders(folders);
+                Catalog::read_folders_content(conn, self.load_mode, &mut folders, &mut errors)?;
             }
+            self.folders.append_root_folders(roots);
+            self.folders.append_folders(folders);
+        }
+        Ok(Loaded::new(&self.folders, errors))
+    }
+
+    /// Read the content of all the containers from `table`. In
+    /// `LoadMode::Lenient` a failure is added to `errors` and `None` is
+    /// returned.
+    fn read_contents(
+        conn: &Connection,
+        mode: LoadMode,
+        table: &str,
+        container_col: &str,
+        errors: &mut Vec<RowError>,
+    ) -> crate::Result<Option<HashMap<LrId, Content>>> {
+        match Content::from_db_all(conn, table, container_col) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(err) if mode == LoadMode::Lenient => {
+                errors.push(RowError::for_table(table, &err));
+                Ok(None)
+            }
+            Err(err) => Err(err),
         }
-        &self.folders
+    }
+
+    /// Set the content of `folders`. It is left unset if it can't be
+    /// read.
+    fn read_folders_content(
+        conn: &Connection,
+        mode: LoadMode,
+        folders: &mut [Folder],
+        errors: &mut Vec<RowError>,
+    ) -> crate::Result<()> {
+        let table = "AgFolderContent";
+        if let Some(mut contents) =
+            Catalog::read_contents(conn, mode, table, "containingFolder", errors)?
+        {
+            for folder in folders {
+                folder.content = Some(contents.remove(&folder.id()).unwrap_or_default());
+            }
+        }
+        Ok(())
+    }
+
+    /// Load a folder tree, with the image counts. This loads the
+    /// folders, the library files and the images: load them first to
+    /// get the rows that failed to load.
+    pub fn load_folder_tree(&mut self) -> crate::Result<FolderTree> {
+        self.load_folders()?;
+        self.load_library_files()?;
+        self.load_images()?;
+
+        let mut tree = FolderTree::from_folders(&self.folders);
+        tree.count_images(&self.images, &self.libfiles);
+
+        Ok(tree)
     }
 
     /// Get the folders. This assume the folders have been loaded first.
@@ -214,15 +744,22 @@ impl Catalog {
         &self.folders
     }
 
+    /// Set the remapping applied to the folder paths, for when the
+    /// files have moved since the catalog was used.
+    pub fn set_path_remap(&mut self, remap: PathRemap) {
+        self.folders.set_remap(remap);
+    }
+
     /// Load library files (that back images)
-    pub fn load_library_files(&mut self) -> &Vec<LibraryFile> {
+    pub fn load_library_files(&mut self) -> crate::Result<Loaded<'_, Vec<LibraryFile>>> {
+        let mut errors = vec![];
         if self.libfiles.is_empty() {
-            if let Some(ref conn) = self.dbconn {
-                let mut result = Catalog::load_objects::<LibraryFile>(conn, self.catalog_version);
-                self.libfiles.append(&mut result);
-            }
+            let (mut result, mut row_errors) = self.load::<LibraryFile>()?;
+            errors.append(&mut row_errors);
+            self.libfile_index.extend(&result, self.libfiles.len());
+            self.libfiles.append(&mut result);
         }
-        &self.libfiles
+        Ok(Loaded::new(&self.libfiles, errors))
     }
 
     /// Get the libfiles. This assume the libfiles have been loaded first.
@@ -232,14 +769,15 @@ impl Catalog {
     }
 
     /// Load images.
-    pub fn load_images(&mut self) -> &Vec<Image> {
+    pub fn load_images(&mut self) -> crate::Result<Loaded<'_, Vec<Image>>> {
+        let mut errors = vec![];
         if self.images.is_empty() {
-            if let Some(ref conn) = self.dbconn {
-                let mut result = Catalog::load_objects::<Image>(conn, self.catalog_version);
-                self.images.append(&mut result);
-            }
+            let (mut result, mut row_errors) = self.load::<Image>()?;
+            errors.append(&mut row_errors);
+            self.image_index.extend(&result, self.images.len());
+            self.images.append(&mut result);
         }
-        &self.images
+        Ok(Loaded::new(&self.images, errors))
     }
 
     /// Get the images. This assume the images have been loaded first.
@@ -249,18 +787,206 @@ impl Catalog {
     }
 
     /// Load collectons.
-    pub fn load_collections(&mut self) -> &Vec<Collection> {
+    pub fn load_collections(&mut self) -> crate::Result<Loaded<'_, Vec<Collection>>> {
+        let mut errors = vec![];
         if self.collections.is_empty() {
+            let (mut collections, mut row_errors) = self.load::<Collection>()?;
+            errors.append(&mut row_errors);
             if let Some(ref conn) = self.dbconn {
-                let mut collections =
-                    Catalog::load_objects::<Collection>(conn, self.catalog_version);
-                for collection in &mut collections {
-                    collection.content = Some(collection.read_content(conn));
+                Catalog::read_collections_content(
+                    conn,
+                    self.load_mode,
+                    &mut collections,
+                    &mut errors,
+                )?;
+            }
+            self.collections.append(&mut collections);
+        }
+        Ok(Loaded::new(&self.collections, errors))
+    }
+
+    /// Set the content of `collections`. It is left unset if it can't
+    /// be read.
+    fn read_collections_content(
+        conn: &Connection,
+        mode: LoadMode,
+        collections: &mut [Collection],
+        errors: &mut Vec<RowError>,
+    ) -> crate::Result<()> {
+        let table = "AgLibraryCollectionContent";
+        if let Some(mut contents) = Catalog::read_contents(conn, mode, table, "collection", errors)?
+        {
+            for collection in collections {
+                collection.content = Some(contents.remove(&collection.id()).unwrap_or_default());
+            }
+        }
+        Ok(())
+    }
+
+    /// Load imports, with their images.
+    pub fn load_imports(&mut self) -> crate::Result<Loaded<'_, Vec<Import>>> {
+        let mut errors = vec![];
+        if self.imports.is_empty() {
+            let (mut imports, mut row_errors) = self.load::<Import>()?;
+            errors.append(&mut row_errors);
+            let conn = self.dbconn.as_ref().ok_or(crate::Error::NotOpen)?;
+            let mut images = Catalog::query_import_images(conn, self.catalog_version)?;
+            for import in &mut imports {
+                import.images = images.remove(&import.id()).unwrap_or_default();
+            }
+            self.imports.append(&mut imports);
+        }
+        Ok(Loaded::new(&self.imports, errors))
+    }
+
+    /// Get the imports. This assume the imports have been loaded first.
+    /// This allow non-mutable borrowing that would be caused by `load_imports()`.
+    pub fn imports(&self) -> &Vec<Import> {
+        &self.imports
+    }
+
+    /// Load everything that isn't loaded yet: keywords, folders,
+    /// library files, images and collections. The steps are loaded
+    /// concurrently, each on its own read-only connection, and
+    /// `progress` is called, from the loading threads, as each step
+    /// is done.
+    ///
+    /// Return the rows that failed to load in `LoadMode::Lenient`.
+    pub fn load_all<F>(&mut self, progress: F) -> crate::Result<Vec<RowError>>
+    where
+        F: Fn(LoadProgress) + Sync,
+    {
+        self.dbconn.as_ref().ok_or(crate::Error::NotOpen)?;
+        let mut steps = vec![];
+        if self.keywords.is_empty() {
+            steps.push(LoadStep::Keywords);
+        }
+        if self.folders.is_empty() {
+            steps.push(LoadStep::Folders);
+        }
+        if self.libfiles.is_empty() {
+            steps.push(LoadStep::LibraryFiles);
+        }
+        if self.images.is_empty() {
+            steps.push(LoadStep::Images);
+        }
+        if self.collections.is_empty() {
+            steps.push(LoadStep::Collections);
+        }
+
+        // A `Connection` can't be shared between threads, but can be
+        // sent to one.
+        let readers = steps
+            .iter()
+            .map(|step| Ok((*step, self.open_reader()?)))
+            .collect::<crate::Result<Vec<_>>>()?;
+        let version = self.catalog_version;
+        let mode = self.load_mode;
+        let total = steps.len();
+        let completed = std::sync::atomic::AtomicUsize::new(0);
+        let loaded = std::thread::scope(|scope| {
+            let threads = readers
+                .into_iter()
+                .map(|(step, conn)| {
+                    let progress = &progress;
+                    let completed = &completed;
+                    scope.spawn(move || {
+                        let loaded = Catalog::load_step(&conn, version, mode, step)?;
+                        let completed =
+                            completed.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                        progress(LoadProgress {
+                            step,
+                            count: loaded.count(),
+                            completed,
+                            total,
+                        });
+                        Ok(loaded)
+                    })
+                })
+                .collect::<Vec<_>>();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().expect("Loading thread panicked"))
+                .collect::<crate::Result<Vec<StepObjects>>>()
+        })?;
+
+        let mut row_errors = vec![];
+        for loaded in loaded {
+            match loaded {
+                StepObjects::Keywords(keywords, mut errors) => {
+                    self.add_keywords(keywords);
+                    row_errors.append(&mut errors);
+                }
+                StepObjects::Folders(roots, folders, mut errors) => {
+                    self.folders.append_root_folders(roots);
+                    self.folders.append_folders(folders);
+                    row_errors.append(&mut errors);
+                }
+                StepObjects::LibraryFiles(mut libfiles, mut errors) => {
+                    self.libfile_index.extend(&libfiles, self.libfiles.len());
+                    self.libfiles.append(&mut libfiles);
+                    row_errors.append(&mut errors);
+                }
+                StepObjects::Images(mut images, mut errors) => {
+                    self.image_index.extend(&images, self.images.len());
+                    self.images.append(&mut images);
+                    row_errors.append(&mut errors);
+                }
+                StepObjects::Collections(mut collections, mut errors) => {
+                    self.collections.append(&mut collections);
+                    row_errors.append(&mut errors);
                 }
-                self.collections.append(&mut collections);
             }
         }
-        &self.collections
+
+        Ok(row_errors)
+    }
+
+    /// Open another read-only connection to the catalog, for loading
+    /// from another thread.
+    fn open_reader(&self) -> crate::Result<Connection> {
+        let conn = self.dbconn.as_ref().ok_or(crate::Error::NotOpen)?;
+        match self.snapshot_path() {
+            Some(path) => Catalog::open_connection(&path, false),
+            None => Catalog::open_connection(&self.path, conn.is_readonly(MAIN_DB)?),
+        }
+    }
+
+    /// Load the objects of `step`.
+    fn load_step(
+        conn: &Connection,
+        version: CatalogVersion,
+        mode: LoadMode,
+        step: LoadStep,
+    ) -> crate::Result<StepObjects> {
+        Ok(match step {
+            LoadStep::Keywords => {
+                let (keywords, errors) = Catalog::load_objects(conn, version, mode)?;
+                StepObjects::Keywords(keywords, errors)
+            }
+            LoadStep::Folders => {
+                let (roots, mut errors) = Catalog::load_objects(conn, version, mode)?;
+                let (mut folders, mut folder_errors) =
+                    Catalog::load_objects::<Folder>(conn, version, mode)?;
+                errors.append(&mut folder_errors);
+                Catalog::read_folders_content(conn, mode, &mut folders, &mut errors)?;
+                StepObjects::Folders(roots, folders, errors)
+            }
+            LoadStep::LibraryFiles => {
+                let (libfiles, errors) = Catalog::load_objects(conn, version, mode)?;
+                StepObjects::LibraryFiles(libfiles, errors)
+            }
+            LoadStep::Images => {
+                let (images, errors) = Catalog::load_objects(conn, version, mode)?;
+                StepObjects::Images(images, errors)
+            }
+            LoadStep::Collections => {
+                let (mut collections, mut errors) =
+                    Catalog::load_objects::<Collection>(conn, version, mode)?;
+                Catalog::read_collections_content(conn, mode, &mut collections, &mut errors)?;
+                StepObjects::Collections(collections, errors)
+            }
+        })
     }
 
     /// Get the collections. This assume the collections have been loaded first.
@@ -269,6 +995,149 @@ impl Catalog {
         &self.collections
     }
 
+    /// Get the image with `id`. This assume the images have been
+    /// loaded first.
+    pub fn image(&self, id: LrId) -> Option<&Image> {
+        self.image_index.get(&self.images, id)
+    }
+
+    /// Get the library file with `id`. This assume the libfiles have
+    /// been loaded first.
+    pub fn library_file(&self, id: LrId) -> Option<&LibraryFile> {
+        self.libfile_index.get(&self.libfiles, id)
+    }
+
+    /// Get the folder with `id`. This assume the folders have been
+    /// loaded first.
+    pub fn folder(&self, id: LrId) -> Option<&Folder> {
+        self.folders.find_folder(id)
+    }
+
+    /// Get the root folder with `id`. This assume the folders have
+    /// been loaded first.
+    pub fn root_folder(&self, id: LrId) -> Option<&RootFolder> {
+        self.folders.find_root_folder(id)
+    }
+
+    /// Get the keyword with `id`. This assume the keywords have been
+    /// loaded first.
+    pub fn keyword(&self, id: LrId) -> Option<&Keyword> {
+        self.keywords.get(&id)
+    }
+
+    /// Get the image with `id` as a navigable `ImageRef`. This assume
+    /// the images, libfiles and folders have been loaded first.
+    pub fn image_ref(&self, id: LrId) -> Option<ImageRef<'_>> {
+        self.image(id).map(|image| ImageRef::new(self, image))
+    }
+
+    /// Return the path of the file backing `image` resolved on disk,
+    /// as found by `locate_image()`: the absolute path unless only the
+    /// path relative to the catalog exists. `ImageRef::path()` is the
+    /// path as stored in the catalog. This assume the libfiles and
+    /// folders have been loaded first.
+    pub fn image_path(&self, image: &Image) -> Option<PathBuf> {
+        self.locate_image(image).map(|located| located.path)
+    }
+
+    /// Locate the file backing `image` on disk, from the absolute path
+    /// or the path relative to the catalog.
+    pub fn locate_image(&self, image: &Image) -> Option<LocatedPath> {
+        ImageRef::new(self, image).locate()
+    }
+
+    /// The files on disk belonging to `image`: the file and its
+    /// sidecars that can be found.
+    pub fn image_files(&self, image: &Image) -> Vec<PathBuf> {
+        ImageRef::new(self, image).files_on_disk()
+    }
+
+    /// Locate `folder` on disk, from the absolute path or the path
+    /// relative to the catalog.
+    pub fn locate_folder(&self, folder: &Folder) -> Option<LocatedPath> {
+        self.folders.locate_folder(folder, &self.directory())
+    }
+
+    /// Return the paths of the sidecars of the file backing `image`, in
+    /// its folder located like with `locate_folder()`. This assume the
+    /// libfiles and folders have been loaded first.
+    pub fn sidecar_paths(&self, image: &Image) -> Vec<PathBuf> {
+        ImageRef::new(self, image)
+            .file()
+            .map(|file| file.sidecar_paths())
+            .unwrap_or_default()
+    }
+
+    /// Get `file` as a navigable `FileRef`.
+    pub fn library_file_ref<'a>(&'a self, file: &'a LibraryFile) -> FileRef<'a> {
+        FileRef::new(self, file)
+    }
+
+    /// Return the path of `file`, located like `image_path()`. This
+    /// assume the folders have been loaded first.
+    pub fn library_file_path(&self, file: &LibraryFile) -> Option<PathBuf> {
+        self.library_file_ref(file)
+            .locate()
+            .map(|located| located.path)
+    }
+
+    /// Find any of the loaded objects by uuid.
+    pub fn by_uuid(&self, uuid: &str) -> Option<CatalogObject<'_>> {
+        self.image_index
+            .get_by_uuid(&self.images, uuid)
+            .map(CatalogObject::Image)
+            .or_else(|| {
+                self.libfile_index
+                    .get_by_uuid(&self.libfiles, uuid)
+                    .map(CatalogObject::LibraryFile)
+            })
+            .or_else(|| {
+                self.folders
+                    .find_folder_by_uuid(uuid)
+                    .map(CatalogObject::Folder)
+            })
+            .or_else(|| {
+                self.folders
+                    .find_root_folder_by_uuid(uuid)
+                    .map(CatalogObject::RootFolder)
+            })
+            .or_else(|| {
+                self.keyword_uuids
+                    .get(uuid)
+                    .and_then(|id| self.keywords.get(id))
+                    .map(CatalogObject::Keyword)
+            })
+    }
+
+    /// Move root folders, in a copy of the catalog written at
+    /// `destination`: the catalog itself is never modified. Every
+    /// folder under each moved root must exist in the new location,
+    /// otherwise `Error::MissingFolders` is returned and nothing is
+    /// written. `destination` must not exist and be valid UTF-8. The
+    /// folders must have been loaded first, otherwise
+    /// `Error::FoldersNotLoaded` is returned.
+    pub fn relocate_roots(
+        &self,
+        relocations: &[RootRelocation],
+        destination: &Path,
+    ) -> crate::Result<()> {
+        let conn = self.dbconn.as_ref().ok_or(crate::Error::NotOpen)?;
+        let missing = relocate::missing_folders(&self.folders, relocations)?;
+        if !missing.is_empty() {
+            return Err(crate::Error::MissingFolders(missing));
+        }
+        if destination.to_str().is_none() {
+            return Err(crate::Error::InvalidPath(destination.to_path_buf()));
+        }
+        // A consistent copy, that fails if `destination` exists. The
+        // connection accepts URIs: pass one so that no character of
+        // `destination` is interpreted.
+        conn.execute("VACUUM INTO ?1", [Catalog::path_uri(destination)])?;
+        relocate::write_relocations(destination, relocations).inspect_err(|_| {
+            let _ = std::fs::remove_file(destination);
+        })
+    }
+
     /// Lr2 use "Tags".
     const LR2_QUERY: &'static str =
         "SELECT image FROM AgLibraryTagImage WHERE tag = ?1 AND tagKind = \"AgCollectionTagKind\"";
@@ -276,13 +1145,13 @@ impl Catalog {
     const LR4_QUERY: &'static str =
         "SELECT image FROM AgLibraryCollectionImage WHERE collection = ?1";
 
-    /// Collect images from collections using a specific query.
-    fn images_for_collection_with_query(
-        &self,
+    /// Collect images from collections or imports using a specific
+    /// query.
+    fn images_with_query(
+        conn: &Connection,
         query: &str,
         collection_id: LrId,
     ) -> super::Result<Vec<LrId>> {
-        let conn = self.dbconn.as_ref().unwrap();
         let mut stmt = conn.prepare(query)?;
         let rows = stmt.query_map([&collection_id], |row| row.get::<usize, i64>(0))?;
         let mut ids = Vec::new();
@@ -295,14 +1164,690 @@ impl Catalog {
     /// Return the of images in the given collection.
     /// Not to be confused with Content.
     pub fn images_for_collection(&self, collection_id: LrId) -> super::Result<Vec<LrId>> {
+        let conn = self.dbconn.as_ref().ok_or(crate::Error::NotOpen)?;
+        Catalog::query_images_for_collection(conn, self.catalog_version, collection_id)
+    }
+
+    pub(crate) fn query_images_for_collection(
+        conn: &Connection,
+        catalog_version: CatalogVersion,
+        collection_id: LrId,
+    ) -> super::Result<Vec<LrId>> {
+        match catalog_version {
+            CatalogVersion::Lr2 => Catalog::images_with_query(conn, Self::LR2_QUERY, collection_id),
+            CatalogVersion::Lr3 | CatalogVersion::Lr4 | CatalogVersion::Lr6 => {
+                Catalog::images_with_query(conn, Self::LR4_QUERY, collection_id)
+            }
+            _ => Err(super::Error::UnsupportedVersion),
+        }
+    }
+
+    /// Lr2 use "Tags".
+    const LR2_IMPORT_QUERY: &'static str =
+        "SELECT image FROM AgLibraryTagImage WHERE tag = ?1 AND tagKind = \"AgImportTagKind\"";
+    /// Lr3, Lr4 and Lr6 store the relation in `AgLibraryImportImage`
+    const LR4_IMPORT_QUERY: &'static str =
+        "SELECT image FROM AgLibraryImportImage WHERE import = ?1";
+
+    /// Return the images imported by the given import.
+    pub fn images_for_import(&self, import_id: LrId) -> super::Result<Vec<LrId>> {
+        let conn = self.dbconn.as_ref().ok_or(crate::Error::NotOpen)?;
         match self.catalog_version {
             CatalogVersion::Lr2 => {
-                self.images_for_collection_with_query(Self::LR2_QUERY, collection_id)
+                Catalog::images_with_query(conn, Self::LR2_IMPORT_QUERY, import_id)
             }
             CatalogVersion::Lr3 | CatalogVersion::Lr4 | CatalogVersion::Lr6 => {
-                self.images_for_collection_with_query(Self::LR4_QUERY, collection_id)
+                Catalog::images_with_query(conn, Self::LR4_IMPORT_QUERY, import_id)
             }
             _ => Err(super::Error::UnsupportedVersion),
         }
     }
+
+    /// Return the images of all the imports, mapped by import id.
+    fn query_import_images(
+        conn: &Connection,
+        catalog_version: CatalogVersion,
+    ) -> super::Result<HashMap<LrId, Vec<LrId>>> {
+        let query = match catalog_version {
+            CatalogVersion::Lr2 => {
+                "SELECT tag, image FROM AgLibraryTagImage \
+                 WHERE tagKind = \"AgImportTagKind\" ORDER BY id_local"
+            }
+            CatalogVersion::Lr3 | CatalogVersion::Lr4 | CatalogVersion::Lr6 => {
+                "SELECT import, image FROM AgLibraryImportImage ORDER BY id_local"
+            }
+            _ => return Err(super::Error::UnsupportedVersion),
+        };
+        let mut stmt = conn.prepare(query)?;
+        let mut rows = stmt.query(params![])?;
+        let mut images = HashMap::<LrId, Vec<LrId>>::new();
+        while let Some(row) = rows.next()? {
+            images.entry(row.get(0)?).or_default().push(row.get(1)?);
+        }
+        Ok(images)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::path::PathBuf;
+
+    use super::{Catalog, CatalogVersion, LoadStep};
+    use crate::folders::PathStrategy;
+    use crate::fromdb::LoadMode;
+    use crate::lrobject::{CatalogObject, LrObject};
+    use crate::remap::{PathRemap, RemapRule};
+
+    /// Create the test catalog from `data/test_catalog.sql`, run
+    /// `extra_sql` on it, and open it. The catalog is removed when
+    /// the `TempDir` is dropped.
+    pub(crate) fn open_test_catalog(extra_sql: &str) -> (tempfile::TempDir, Catalog) {
+        let dir = tempfile::tempdir().expect("Couldn't create temp dir");
+        let path = dir.path().join("test.lrcat");
+        {
+            let conn = rusqlite::Connection::open(&path).expect("Couldn't create catalog");
+            conn.execute_batch(include_str!("../data/test_catalog.sql"))
+                .expect("Couldn't create tables");
+            conn.execute_batch(extra_sql).expect("Couldn't run SQL");
+        }
+
+        let mut catalog = Catalog::new(&path);
+        catalog.open().expect("Couldn't open catalog");
+        assert_eq!(catalog.catalog_version, CatalogVersion::Lr6);
+
+        (dir, catalog)
+    }
+
+    #[test]
+    fn test_open_read_only() {
+        let (dir, catalog) = open_test_catalog("");
+        let path = catalog.path.clone();
+        drop(catalog);
+        let files = || {
+            let mut files = std::fs::read_dir(dir.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect::<Vec<_>>();
+            files.sort();
+            files
+        };
+        let before = files();
+
+        let mut catalog = Catalog::new(&path);
+        catalog.open_read_only().expect("Couldn't open read only");
+        assert_eq!(catalog.catalog_version, CatalogVersion::Lr6);
+        assert_eq!(
+            catalog.load_images().map(|images| images.len()).ok(),
+            Some(4)
+        );
+        assert_eq!(files(), before);
+        drop(catalog);
+
+        // Other files are ignored.
+        std::fs::write(dir.path().join("test.lrcat.bak"), "").unwrap();
+        let mut catalog = Catalog::new(&path);
+        catalog.open_read_only().expect("Couldn't open read-only");
+        drop(catalog);
+
+        // Lightroom has it open.
+        let lock = dir.path().join("test.lrcat-lock");
+        std::fs::write(&lock, "").unwrap();
+        let mut catalog = Catalog::new(&path);
+        assert!(matches!(
+            catalog.open_read_only(),
+            Err(crate::Error::CatalogLocked(ref p)) if p == &lock
+        ));
+
+        #[cfg(feature = "snapshot")]
+        {
+            catalog.open_snapshot().expect("Couldn't open snapshot");
+            assert_eq!(catalog.catalog_version, CatalogVersion::Lr6);
+            assert_eq!(
+                catalog.load_images().map(|images| images.len()).ok(),
+                Some(4)
+            );
+        }
+    }
+
+    #[cfg(feature = "snapshot")]
+    #[test]
+    fn test_open_snapshot() {
+        let (_dir, catalog) = open_test_catalog("");
+        let path = catalog.path.clone();
+        drop(catalog);
+
+        // Lightroom has the catalog open, with changes in the WAL.
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL; PRAGMA wal_autocheckpoint = 0; \
+             UPDATE AgLibraryRootFolder SET name = 'Moved' WHERE id_local = 10;",
+        )
+        .unwrap();
+        assert!(path.with_extension("lrcat-wal").exists());
+
+        let mut catalog = Catalog::new(&path);
+        catalog.open_snapshot().expect("Couldn't open snapshot");
+        catalog.load_folders().unwrap();
+        assert_eq!(catalog.root_folder(10).unwrap().name, "Moved");
+        drop(conn);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_path_uri() {
+        assert_eq!(
+            Catalog::path_uri(std::path::Path::new("/data/a\\b?#%é 1.lrcat")),
+            "file:/data/a%5Cb%3F%23%25%C3%A9%201.lrcat"
+        );
+
+        let (dir, catalog) = open_test_catalog("");
+        let path = dir.path().join("a\\b?#%é.lrcat");
+        std::fs::rename(catalog.path(), &path).unwrap();
+        drop(catalog);
+        let mut catalog = Catalog::new(&path);
+        catalog.open_read_only().expect("Couldn't open read only");
+        assert_eq!(catalog.catalog_version, CatalogVersion::Lr6);
+    }
+
+    #[test]
+    fn test_open_errors() {
+        let (dir, catalog) = open_test_catalog("");
+        let path = catalog.path.clone();
+        drop(catalog);
+        let open = |path: &std::path::Path| {
+            let mut catalog = Catalog::new(path);
+            catalog.open().map(|_| catalog)
+        };
+
+        assert!(matches!(
+            open(&dir.path().join("missing.lrcat")),
+            Err(crate::Error::Io(_))
+        ));
+        assert!(matches!(
+            open(std::path::Path::new(":memory:")),
+            Err(crate::Error::NotLightroomCatalog)
+        ));
+
+        let other = dir.path().join("other.lrcat");
+        std::fs::write(&other, "").unwrap();
+        assert!(matches!(open(&other), Err(crate::Error::NotSqlite)));
+        std::fs::write(&other, "This is not a catalog, but it is long enough.").unwrap();
+        assert!(matches!(open(&other), Err(crate::Error::NotSqlite)));
+
+        let mut data = std::fs::read(&path).unwrap();
+        data[16..100].fill(0xff);
+        std::fs::write(&other, &data).unwrap();
+        assert!(matches!(open(&other), Err(crate::Error::Corrupt(_))));
+
+        std::fs::remove_file(&other).unwrap();
+        rusqlite::Connection::open(&other)
+            .unwrap()
+            .execute_batch("CREATE TABLE t (id INTEGER);")
+            .unwrap();
+        assert!(matches!(
+            open(&other),
+            Err(crate::Error::NotLightroomCatalog)
+        ));
+
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute(
+                "UPDATE Adobe_variablesTable SET value = '1300025' WHERE name = 'Adobe_DBVersion'",
+                [],
+            )
+            .unwrap();
+            // Lightroom Classic: opened, but reported.
+            let catalog = open(&path).expect("Couldn't open catalog");
+            assert_eq!(catalog.catalog_version, CatalogVersion::Unknown);
+            assert!(matches!(
+                catalog.validate(),
+                Err(crate::Error::VersionTooNew(ref v)) if v == "1300025"
+            ));
+            drop(catalog);
+            conn.execute(
+                "UPDATE Adobe_variablesTable SET value = '0500020' WHERE name = 'Adobe_DBVersion'",
+                [],
+            )
+            .unwrap();
+            let catalog = open(&path).expect("Couldn't open catalog");
+            assert!(matches!(
+                catalog.validate(),
+                Err(crate::Error::UnsupportedVersion)
+            ));
+            drop(catalog);
+            conn.execute(
+                "UPDATE Adobe_variablesTable SET value = '0600008' WHERE name = 'Adobe_DBVersion'",
+                [],
+            )
+            .unwrap();
+
+            // Lightroom keeps an exclusive lock.
+            conn.execute_batch("PRAGMA locking_mode = EXCLUSIVE; BEGIN EXCLUSIVE;")
+                .unwrap();
+            assert!(matches!(open(&path), Err(crate::Error::CatalogLocked(_))));
+        }
+
+        let catalog = open(&path).expect("Couldn't open catalog");
+        assert_eq!(catalog.catalog_version, CatalogVersion::Lr6);
+        assert!(catalog.validate().is_ok());
+        assert_eq!(catalog.root_keyword_id, 1);
+    }
+
+    #[test]
+    fn test_iter_images() {
+        // captureTime can't be NULL.
+        const SQL: &str = "INSERT INTO Adobe_images (id_local, id_global, fileFormat, rootFile) \
+                           VALUES (50, 'E1C1D1E1-0000-4000-8000-000000000050', 'RAW', 20);";
+        let (_dir, catalog) = open_test_catalog(SQL);
+
+        let images = catalog
+            .iter_images()
+            .expect("Couldn't iterate")
+            .page_size(3)
+            .collect::<Vec<_>>();
+        assert_eq!(images.len(), 5);
+        let ids = images
+            .iter()
+            .filter_map(|image| image.as_ref().ok())
+            .map(|image| image.id())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![30, 31, 32, 33]);
+        assert!(matches!(images[4], Err(crate::Error::Row(ref e)) if e.id_local == Some(50)));
+
+        assert_eq!(
+            catalog
+                .iter_collections()
+                .expect("Couldn't iterate")
+                .page_size(1)
+                .filter_map(Result::ok)
+                .count(),
+            4
+        );
+        assert!(catalog.images().is_empty());
+    }
+
+    #[test]
+    fn test_lookups() {
+        let (_dir, mut catalog) = open_test_catalog("");
+        catalog.load_keywords().unwrap();
+        catalog.load_folders().unwrap();
+        catalog.load_library_files().unwrap();
+        catalog.load_images().unwrap();
+
+        let image = catalog.image(31).expect("Image not found");
+        let file = catalog
+            .library_file(image.root_file)
+            .expect("File not found");
+        let folder = catalog.folder(file.folder).expect("Folder not found");
+        let root = catalog
+            .root_folder(folder.root_folder)
+            .expect("Root not found");
+        assert_eq!(file.basename, "IMG_0002");
+        assert_eq!(folder.path_from_root, "2017/10/");
+        assert_eq!(root.name, "Pictures");
+        assert!(catalog.image(20).is_none());
+
+        assert!(matches!(
+            catalog.by_uuid("E1C1D1E1-0000-4000-8000-000000000031"),
+            Some(CatalogObject::Image(i)) if i.id() == 31
+        ));
+        assert!(matches!(
+            catalog.by_uuid("C1C1D1E1-0000-4000-8000-000000000010"),
+            Some(CatalogObject::RootFolder(_))
+        ));
+        let keyword = catalog.by_uuid("B1C1D1E1-0000-4000-8000-000000000003");
+        assert_eq!(keyword.map(|k| k.id()), Some(3));
+        assert!(catalog.by_uuid("nope").is_none());
+    }
+
+    #[test]
+    fn test_image_path() {
+        let (_dir, mut catalog) = open_test_catalog("");
+        catalog.load_folders().unwrap();
+        catalog.load_library_files().unwrap();
+        catalog.load_images().unwrap();
+
+        let image = catalog.image_ref(30).expect("Image not found");
+        let file = image.file().expect("File not found");
+        assert_eq!(file.basename, "IMG_0001");
+        let folder = file.folder().expect("Folder not found");
+        assert_eq!(folder.id(), 13);
+        assert_eq!(folder.root().map(|root| root.id()), Some(10));
+
+        assert_eq!(
+            catalog.image_path(image.image()),
+            Some(PathBuf::from("/home/user/Pictures/2017/10/IMG_0001.CR2"))
+        );
+        assert_eq!(
+            catalog.sidecar_paths(image.image()),
+            vec![
+                PathBuf::from("/home/user/Pictures/2017/10/IMG_0001.JPG"),
+                PathBuf::from("/home/user/Pictures/2017/10/IMG_0001.xmp"),
+            ]
+        );
+
+        let image = catalog.image(33).unwrap();
+        assert_eq!(
+            catalog.image_path(image),
+            Some(PathBuf::from("/home/user/Pictures/2017/DSC_0004.DNG"))
+        );
+        assert!(catalog.sidecar_paths(image).is_empty());
+    }
+
+    #[test]
+    fn test_load_all() {
+        const SQL: &str = "UPDATE AgLibraryFile SET folder = 'none' WHERE id_local = 23;";
+        let (_dir, mut catalog) = open_test_catalog(SQL);
+        catalog.load_keywords().unwrap();
+
+        let progress = std::sync::Mutex::new(vec![]);
+        let errors = catalog
+            .load_all(|p| progress.lock().unwrap().push(p))
+            .expect("Couldn't load all");
+        let mut progress = progress.into_inner().unwrap();
+        progress.sort_by_key(|p| p.completed);
+        assert_eq!(
+            progress.iter().map(|p| p.completed).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+        assert!(progress.iter().all(|p| p.total == 4));
+        assert!(progress
+            .iter()
+            .any(|p| p.step == LoadStep::Images && p.count == 4));
+        assert!(progress.iter().all(|p| p.step != LoadStep::Keywords));
+
+        assert_eq!(catalog.keywords().len(), 4);
+        assert_eq!(catalog.folders().folders().len(), 3);
+        assert!(catalog.folder(13).unwrap().content.is_some());
+        assert_eq!(catalog.libfiles().len(), 3);
+        assert_eq!(catalog.images().len(), 4);
+        assert_eq!(catalog.image(33).map(|image| image.root_file), Some(23));
+        assert_eq!(catalog.collections().len(), 4);
+        assert_eq!(errors.len(), 1);
+
+        let (_dir, mut catalog) = open_test_catalog(SQL);
+        catalog.set_load_mode(LoadMode::Strict);
+        assert!(matches!(
+            catalog.load_all(|_| {}),
+            Err(crate::Error::Row(ref e)) if e.id_local == Some(23)
+        ));
+    }
+
+    #[test]
+    fn test_locate_image() {
+        const SQL: &str = "UPDATE AgLibraryRootFolder SET relativePathFromCatalog = 'Photos/';";
+        let (dir, mut catalog) = open_test_catalog(SQL);
+        catalog.load_folders().unwrap();
+        catalog.load_library_files().unwrap();
+        catalog.load_images().unwrap();
+        let photos = dir.path().join("Photos");
+        std::fs::create_dir_all(photos.join("2017/10")).unwrap();
+        std::fs::write(photos.join("2017/10/IMG_0001.CR2"), "").unwrap();
+
+        let located = catalog.locate_image(catalog.image(30).unwrap()).unwrap();
+        assert_eq!(located.strategy, PathStrategy::RelativeToCatalog);
+        assert_eq!(located.path, photos.join("2017/10/IMG_0001.CR2"));
+        let located = catalog.locate_image(catalog.image(31).unwrap()).unwrap();
+        assert_eq!(located.strategy, PathStrategy::NotFound);
+        assert_eq!(
+            located.path,
+            PathBuf::from("/home/user/Pictures/2017/10/IMG_0002.JPG")
+        );
+        let located = catalog.locate_folder(catalog.folder(13).unwrap()).unwrap();
+        assert_eq!(located.strategy, PathStrategy::RelativeToCatalog);
+        assert_eq!(
+            catalog.image_path(catalog.image(30).unwrap()),
+            Some(photos.join("2017/10/IMG_0001.CR2"))
+        );
+        assert_eq!(
+            catalog.image_path(catalog.image(31).unwrap()),
+            Some(PathBuf::from("/home/user/Pictures/2017/10/IMG_0002.JPG"))
+        );
+
+        std::fs::write(photos.join("2017/10/IMG_0001.xmp"), "").unwrap();
+        assert_eq!(
+            catalog.image_files(catalog.image(30).unwrap()),
+            vec![
+                photos.join("2017/10/IMG_0001.CR2"),
+                photos.join("2017/10/IMG_0001.xmp")
+            ]
+        );
+        assert!(catalog.image_files(catalog.image(31).unwrap()).is_empty());
+        assert_eq!(
+            catalog.sidecar_paths(catalog.image(30).unwrap()),
+            vec![
+                photos.join("2017/10/IMG_0001.JPG"),
+                photos.join("2017/10/IMG_0001.xmp")
+            ]
+        );
+
+        let mut remap = PathRemap::new();
+        remap.add_rule(RemapRule::new(
+            "/home/user/Pictures",
+            &photos.to_string_lossy(),
+        ));
+        catalog.set_path_remap(remap);
+        let located = catalog.locate_image(catalog.image(30).unwrap()).unwrap();
+        assert_eq!(located.strategy, PathStrategy::Absolute);
+        assert_eq!(located.path, photos.join("2017/10/IMG_0001.CR2"));
+    }
+
+    #[test]
+    fn test_library_file_attributes() {
+        const SQL: &str = "UPDATE AgLibraryFile SET sidecarExtensions = NULL, \
+                           md5 = 'd41d8cd98f00b204e9800998ecf8427e', baseName = 'Renamed' \
+                           WHERE id_local = 23;";
+        let (dir, mut catalog) = open_test_catalog(SQL);
+        assert!(catalog.load_library_files().unwrap().errors.is_empty());
+        assert!(catalog.load_folders().unwrap().errors.is_empty());
+
+        let file = catalog.library_file(20).unwrap();
+        assert_eq!(file.original_filename, "IMG_0001.CR2");
+        assert_eq!(file.idx_filename, "IMG_0001.CR2");
+        assert_eq!(file.import_hash.as_deref(), Some("2017-10-01T10:00:00-1"));
+        assert_eq!(file.md5, None);
+        assert_eq!(
+            file.external_mod_time.map(|t| t.to_rfc3339()).as_deref(),
+            Some("2017-10-06T16:26:40+00:00")
+        );
+        assert!(!file.is_renamed());
+
+        let file = catalog.library_file(23).unwrap();
+        assert_eq!(file.sidecar_extensions, "");
+        assert_eq!(
+            file.md5.as_deref(),
+            Some("d41d8cd98f00b204e9800998ecf8427e")
+        );
+        assert!(file.is_renamed());
+        assert_eq!(file.original_filename, "DSC_0004.DNG");
+
+        let mut remap = PathRemap::new();
+        remap.add_rule(RemapRule::new(
+            "/home/user/Pictures",
+            &dir.path().to_string_lossy(),
+        ));
+        catalog.set_path_remap(remap);
+        std::fs::create_dir_all(dir.path().join("2017")).unwrap();
+        std::fs::write(dir.path().join("2017/Renamed.DNG"), "1234").unwrap();
+        let file = catalog.library_file(23).unwrap();
+        assert_eq!(catalog.library_file_ref(file).size(), Some(4));
+        let file = catalog.library_file(22).unwrap();
+        assert_eq!(catalog.library_file_ref(file).size(), None);
+
+        // An older catalog without the extended attributes.
+        const OLD_SQL: &str = "ALTER TABLE AgLibraryFile DROP COLUMN originalFilename; \
+                               ALTER TABLE AgLibraryFile DROP COLUMN idx_filename; \
+                               ALTER TABLE AgLibraryFile DROP COLUMN md5; \
+                               ALTER TABLE AgLibraryFile DROP COLUMN importHash; \
+                               ALTER TABLE AgLibraryFile DROP COLUMN externalModTime;";
+        let (_dir, mut catalog) = open_test_catalog(OLD_SQL);
+        catalog.catalog_version = CatalogVersion::Lr2;
+        let files = catalog.load_library_files().unwrap();
+        assert_eq!(files.len(), 4);
+        assert!(files.errors.is_empty());
+        let file = catalog.library_file(20).unwrap();
+        assert_eq!(file.basename, "IMG_0001");
+        assert_eq!(file.original_filename, "");
+        assert_eq!(file.md5, None);
+        assert_eq!(file.external_mod_time, None);
+    }
+
+    #[test]
+    fn test_genealogy() {
+        const SQL: &str =
+            "UPDATE AgLibraryKeyword SET genealogy = '/11/14/13' WHERE id_local = 3; \
+                           UPDATE AgLibraryCollection SET genealogy = 'bogus' WHERE id_local = 43;";
+        let (_dir, mut catalog) = open_test_catalog(SQL);
+        catalog.load_keywords().unwrap();
+        catalog.load_collections().unwrap();
+
+        let keyword = catalog.keyword(2).unwrap();
+        assert_eq!(
+            keyword.genealogy.as_ref().map(|g| g.ids().to_vec()),
+            Some(vec![1, 2])
+        );
+        assert!(keyword.check_genealogy());
+        assert!(catalog.keyword(1).unwrap().check_genealogy());
+        assert!(!catalog.keyword(3).unwrap().check_genealogy());
+
+        let collections = catalog.collections();
+        let collection = collections.iter().find(|c| c.id() == 42).unwrap();
+        assert_eq!(
+            collection
+                .genealogy
+                .as_ref()
+                .map(|g| g.ancestors().to_vec()),
+            Some(vec![41])
+        );
+        assert!(collections
+            .iter()
+            .filter(|c| c.id() != 43)
+            .all(|c| c.check_genealogy()));
+        let bogus = collections.iter().find(|c| c.id() == 43).unwrap();
+        assert!(bogus.genealogy.is_none());
+        assert_eq!(bogus.invalid_genealogy.as_deref(), Some("bogus"));
+        assert!(!bogus.check_genealogy());
+    }
+
+    #[test]
+    fn test_imports() {
+        let (_dir, mut catalog) = open_test_catalog("");
+
+        let imports = catalog.load_imports().unwrap();
+        assert_eq!(imports.len(), 2);
+        let import = &imports[0];
+        assert_eq!(import.id(), 50);
+        assert_eq!(import.name, None);
+        assert_eq!(import.date, "2017-10-03T09:00:00");
+        assert_eq!(import.image_count, Some(2));
+        assert_eq!(import.images, vec![30, 31]);
+        let import = &imports[1];
+        assert_eq!(import.name.as_deref(), Some("Trip"));
+        assert_eq!(import.images, vec![32, 33]);
+
+        assert_eq!(catalog.images_for_import(51).unwrap(), vec![32, 33]);
+        assert!(catalog.images_for_import(42).unwrap().is_empty());
+
+        // Lr3 has the same tables.
+        let (_dir, mut catalog) = open_test_catalog("");
+        catalog.catalog_version = CatalogVersion::Lr3;
+        let imports = catalog.load_imports().unwrap();
+        assert_eq!(imports.len(), 2);
+        assert!(imports.errors.is_empty());
+        assert_eq!(imports[1].images, vec![32, 33]);
+    }
+
+    #[test]
+    fn test_load_images_missing_rows() {
+        let (_dir, mut catalog) = open_test_catalog("");
+
+        let images = catalog.load_images().expect("Failed to load images");
+        assert_eq!(images.len(), 4);
+
+        let image = images.iter().find(|i| i.id() == 30).unwrap();
+        assert!(image.xmp.is_some());
+        assert_eq!(image.xmp_embedded, Some(false));
+        assert!(image.properties.is_some());
+
+        let image = images.iter().find(|i| i.id() == 31).unwrap();
+        assert!(image.xmp.is_none());
+        assert!(image.xmp_embedded.is_none());
+        assert!(image.properties.is_some());
+
+        let image = images.iter().find(|i| i.id() == 32).unwrap();
+        assert!(image.xmp.is_some());
+        assert_eq!(image.xmp_embedded, Some(true));
+        assert!(image.properties.is_none());
+
+        let image = images.iter().find(|i| i.id() == 33).unwrap();
+        assert!(image.xmp.is_none());
+        assert!(image.xmp_external_dirty.is_none());
+        assert!(image.properties.is_none());
+    }
+
+    #[test]
+    fn test_properties_warnings() {
+        const SQL: &str = "UPDATE Adobe_imageProperties \
+                           SET propertiesString = 'properties = { cropAspectH = 2, bad = @, cropAspectW = 3, }' \
+                           WHERE image = 30;";
+
+        let (_dir, mut catalog) = open_test_catalog(SQL);
+        let images = catalog.load_images().expect("Failed to load images");
+        assert_eq!(images.len(), 4);
+        assert_eq!(images.errors.len(), 1);
+        let error = &images.errors[0];
+        assert_eq!(error.table, "Adobe_images");
+        assert_eq!(error.id_local, Some(30));
+        assert_eq!(error.column.as_deref(), Some("propertiesString"));
+        assert!(error.error.contains("bad = @"));
+        assert!(catalog.image(30).unwrap().properties.is_some());
+
+        let (_dir, mut catalog) = open_test_catalog(SQL);
+        catalog.set_load_mode(LoadMode::Strict);
+        assert!(matches!(catalog.load_images(), Err(crate::Error::Row(_))));
+    }
+
+    #[test]
+    fn test_load_mode() {
+        // captureTime can't be NULL.
+        const SQL: &str = "INSERT INTO Adobe_images (id_local, id_global, fileFormat, rootFile) \
+                           VALUES (50, 'E1C1D1E1-0000-4000-8000-000000000050', 'RAW', 20);";
+
+        let (_dir, mut catalog) = open_test_catalog(SQL);
+        let images = catalog.load_images().expect("Failed to load images");
+        assert_eq!(images.len(), 4);
+        let errors = &images.errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].table, "Adobe_images");
+        assert_eq!(errors[0].id_local, Some(50));
+        assert_eq!(errors[0].column.as_deref(), Some("captureTime"));
+
+        let (_dir, mut catalog) = open_test_catalog(SQL);
+        catalog.set_load_mode(LoadMode::Strict);
+        assert!(matches!(catalog.load_images(), Err(crate::Error::Row(_))));
+
+        // Already loaded: no errors.
+        let (_dir, mut catalog) = open_test_catalog(SQL);
+        assert_eq!(catalog.load_images().unwrap().errors.len(), 1);
+        assert!(catalog.load_images().unwrap().errors.is_empty());
+    }
+
+    #[test]
+    fn test_content_errors() {
+        const SQL: &str = "DROP TABLE AgFolderContent;";
+
+        let (_dir, mut catalog) = open_test_catalog(SQL);
+        let folders = catalog.load_folders().expect("Failed to load folders");
+        assert!(folders
+            .folders()
+            .iter()
+            .all(|folder| folder.content.is_none()));
+        assert_eq!(folders.errors.len(), 1);
+        assert_eq!(folders.errors[0].table, "AgFolderContent");
+        assert_eq!(folders.errors[0].id_local, None);
+
+        let (_dir, mut catalog) = open_test_catalog(SQL);
+        catalog.set_load_mode(LoadMode::Strict);
+        assert!(matches!(catalog.load_folders(), Err(crate::Error::Sql(_))));
+    }
 }
@@ -9,6 +9,7 @@ use rusqlite::{Connection, Row};
 use crate::catalog::CatalogVersion;
 use crate::content::Content;
 use crate::fromdb::FromDb;
+use crate::genealogy::Genealogy;
 use crate::lrobject::LrId;
 
 /// A collection as defined in Lightroom
@@ -19,6 +20,11 @@ pub struct Collection {
     pub name: String,
     /// Parent of the `Collection`
     pub parent: LrId,
+    /// The path in the collection hierarchy. `None` if missing or
+    /// invalid.
+    pub genealogy: Option<Genealogy>,
+    /// The genealogy string if it couldn't be parsed.
+    pub invalid_genealogy: Option<String>,
     /// is system only (seems to be the Quick Pick collection)
     pub system_only: bool,
     /// content definition of the collection
@@ -27,14 +33,20 @@ pub struct Collection {
 
 impl FromDb for Collection {
     fn read_from(version: CatalogVersion, row: &Row) -> crate::Result<Self> {
+        let genealogy = |idx| Genealogy::from_column(row.get(idx).ok());
         match version {
-            CatalogVersion::Lr4 | CatalogVersion::Lr6 => Ok(Collection {
-                id: row.get(0)?,
-                name: row.get(2)?,
-                parent: row.get(3).unwrap_or(0),
-                system_only: !matches!(row.get::<usize, f64>(4)? as i64, 0),
-                content: None,
-            }),
+            CatalogVersion::Lr4 | CatalogVersion::Lr6 => {
+                let (genealogy, invalid_genealogy) = genealogy(1);
+                Ok(Collection {
+                    id: row.get(0)?,
+                    name: row.get(2)?,
+                    parent: row.get(3).unwrap_or(0),
+                    genealogy,
+                    invalid_genealogy,
+                    system_only: !matches!(row.get::<usize, f64>(4)? as i64, 0),
+                    content: None,
+                })
+            }
             CatalogVersion::Lr2 => {
                 let tag_type: Box<str> = row.get(3)?;
                 let name: String = row.get(1).unwrap_or_else(|_| {
@@ -46,13 +58,18 @@ impl FromDb for Collection {
                     .to_owned()
                 });
                 match tag_type.as_ref() {
-                    "AgQuickCollectionTagKind" | "AgCollectionTagKind" => Ok(Collection {
-                        id: row.get(0)?,
-                        name,
-                        parent: row.get(2).unwrap_or(0),
-                        system_only: matches!(tag_type.as_ref(), "AgQuickCollectionTagKind"),
-                        content: None,
-                    }),
+                    "AgQuickCollectionTagKind" | "AgCollectionTagKind" => {
+                        let (genealogy, invalid_genealogy) = genealogy(4);
+                        Ok(Collection {
+                            id: row.get(0)?,
+                            name,
+                            parent: row.get(2).unwrap_or(0),
+                            genealogy,
+                            invalid_genealogy,
+                            system_only: matches!(tag_type.as_ref(), "AgQuickCollectionTagKind"),
+                            content: None,
+                        })
+                    }
                     _ => Err(crate::Error::Skip),
                 }
             }
@@ -73,7 +90,7 @@ impl FromDb for Collection {
             CatalogVersion::Lr4 | CatalogVersion::Lr6 => {
                 "id_local,genealogy,name,parent,systemOnly"
             }
-            CatalogVersion::Lr2 => "id_local,name,parent,kindName",
+            CatalogVersion::Lr2 => "id_local,name,parent,kindName,genealogy",
             _ => "",
         }
     }
@@ -85,8 +102,20 @@ impl Collection {
         self.id
     }
 
+    /// Check that the genealogy, if any, matches `parent`. An invalid
+    /// genealogy doesn't.
+    pub fn check_genealogy(&self) -> bool {
+        if self.invalid_genealogy.is_some() {
+            return false;
+        }
+        self.genealogy
+            .as_ref()
+            .map(|genealogy| genealogy.is_consistent(self.id, self.parent))
+            .unwrap_or(true)
+    }
+
     /// Read the `content` for this collection from the database.
-    pub fn read_content(&self, conn: &Connection) -> Content {
+    pub fn read_content(&self, conn: &Connection) -> crate::Result<Content> {
         Content::from_db(conn, "AgLibraryCollectionContent", "collection", self.id)
     }
 }
@@ -4,10 +4,13 @@
  file, You can obtain one at http://mozilla.org/MPL/2.0/.
 */
 
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 
-use rusqlite::Connection;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, Row};
 
+use crate::lrobject::LrId;
 use crate::lron;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -31,7 +34,9 @@ pub struct Content {
     /// Which direction to sort
     pub sort_direction: Option<SortDirection>,
     /// Define the smart collection (if any)
-    pub smart_collection: Option<lron::Object>,
+    pub smart_collection: Option<lron::Value>,
+    /// All the values, mapped by owning module.
+    pub values: BTreeMap<String, String>,
 }
 
 impl fmt::Debug for Content {
@@ -66,46 +71,141 @@ impl fmt::Debug for Content {
 }
 
 impl Content {
+    /// Read the content for the container `container_id`. `table` is
+    /// the content table, `container_col` the column referencing the
+    /// container. Values without an owning module are ignored.
     pub fn from_db(
         conn: &Connection,
         table: &str,
         container_col: &str,
-        container_id: i64,
-    ) -> Content {
+        container_id: LrId,
+    ) -> crate::Result<Content> {
         let mut content = Content::default();
 
         let query = format!("SELECT content, owningModule from {table} where {container_col}=?1",);
-        if let Ok(mut stmt) = conn.prepare(&query) {
-            let mut rows = stmt.query([&container_id]).unwrap();
-            while let Ok(Some(row)) = rows.next() {
-                // We ignore the result.
-                // XXX shall we display a warning on error? likely
-                let _ = row.get(1).map(|owning_module: String| {
-                    let value = row.get(0);
-                    match owning_module.as_str() {
-                        "com.adobe.ag.library.filter" => content.filter = value.ok(),
-                        "com.adobe.ag.library.sortType" => content.sort_type = value.ok(),
-                        "com.adobe.ag.library.sortDirection" => {
-                            content.sort_direction = if let Ok(sd) = value {
-                                match sd.as_str() {
-                                    "ascending" => Some(SortDirection::Ascending),
-                                    "descending" => Some(SortDirection::Descending),
-                                    _ => Some(SortDirection::Unknown),
-                                }
-                            } else {
-                                None
-                            }
-                        }
-                        "ag.library.smart_collection" => {
-                            if let Ok(ref sc) = value {
-                                content.smart_collection = lron::Object::from_string(sc).ok();
-                            }
-                        }
-                        _ => (),
-                    };
-                });
+        let mut stmt = conn.prepare(&query)?;
+        let mut rows = stmt.query([&container_id])?;
+        while let Some(row) = rows.next()? {
+            if let Some(owning_module) = row.get::<usize, Option<String>>(1)? {
+                content.add_value(&owning_module, Content::text_value(row, 0));
             }
         }
-        content
+        Ok(content)
+    }
+
+    /// Read the content of all the containers at once, mapped by
+    /// container id. `table` is the content table, `container_col` the
+    /// column referencing the container.
+    pub fn from_db_all(
+        conn: &Connection,
+        table: &str,
+        container_col: &str,
+    ) -> crate::Result<HashMap<LrId, Content>> {
+        let mut contents = HashMap::<LrId, Content>::new();
+
+        let query = format!("SELECT {container_col}, content, owningModule from {table}");
+        let mut stmt = conn.prepare(&query)?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            if let (Some(container_id), Some(owning_module)) = (
+                row.get::<usize, Option<LrId>>(0)?,
+                row.get::<usize, Option<String>>(2)?,
+            ) {
+                contents
+                    .entry(container_id)
+                    .or_default()
+                    .add_value(&owning_module, Content::text_value(row, 1));
+            }
+        }
+        Ok(contents)
+    }
+
+    /// Get the value of column `idx` as text. Numbers are formatted.
+    fn text_value(row: &Row, idx: usize) -> Option<String> {
+        match row.get_ref(idx).ok()? {
+            ValueRef::Text(text) => Some(String::from_utf8_lossy(text).into_owned()),
+            ValueRef::Integer(i) => Some(i.to_string()),
+            ValueRef::Real(f) => Some(f.to_string()),
+            ValueRef::Null | ValueRef::Blob(_) => None,
+        }
+    }
+
+    /// Add the `value` for `owning_module`.
+    fn add_value(&mut self, owning_module: &str, value: Option<String>) {
+        match owning_module {
+            "com.adobe.ag.library.filter" => self.filter = value.clone(),
+            "com.adobe.ag.library.sortType" => self.sort_type = value.clone(),
+            "com.adobe.ag.library.sortDirection" => {
+                self.sort_direction = value.as_ref().map(|sd| match sd.as_str() {
+                    "ascending" => SortDirection::Ascending,
+                    "descending" => SortDirection::Descending,
+                    _ => SortDirection::Unknown,
+                })
+            }
+            "ag.library.smart_collection" => {
+                if let Some(ref sc) = value {
+                    self.smart_collection = lron::Value::from_string(sc).ok();
+                }
+            }
+            _ => (),
+        };
+        if let Some(value) = value {
+            self.values.insert(owning_module.to_string(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Content, SortDirection};
+    use crate::catalog::tests::open_test_catalog;
+
+    #[test]
+    fn test_content_from_db_all() {
+        const SQL: &str = "INSERT INTO AgLibraryCollectionContent (id_local, collection, content, owningModule) VALUES \
+                           (70, 42, 'true', 'com.adobe.ag.library.filter_locked'), \
+                           (71, 42, 3, 'com.adobe.ag.library.thumbnailSize');";
+        let (_dir, mut catalog) = open_test_catalog(SQL);
+        catalog.load_collections().unwrap();
+        catalog.load_folders().unwrap();
+        let collection = catalog
+            .collections()
+            .iter()
+            .find(|collection| collection.name == "Montréal")
+            .unwrap();
+        let content = collection.content.as_ref().unwrap();
+        assert_eq!(content.sort_type.as_deref(), Some("captureTime"));
+        assert_eq!(content.sort_direction, Some(SortDirection::Ascending));
+        assert_eq!(
+            content
+                .values
+                .get("com.adobe.ag.library.filter_locked")
+                .map(String::as_str),
+            Some("true")
+        );
+        assert_eq!(
+            content
+                .values
+                .get("com.adobe.ag.library.thumbnailSize")
+                .map(String::as_str),
+            Some("3")
+        );
+        assert_eq!(content.values.len(), 4);
+
+        let folder = catalog.folder(13).unwrap();
+        assert_eq!(
+            folder.content.as_ref().and_then(|c| c.sort_type.as_deref()),
+            Some("fileName")
+        );
+
+        let conn = rusqlite::Connection::open(catalog.path()).unwrap();
+        let content =
+            Content::from_db(&conn, "AgLibraryCollectionContent", "collection", 42).unwrap();
+        assert_eq!(content.values, collection.content.as_ref().unwrap().values);
+
+        assert!(matches!(
+            Content::from_db(&conn, "AgNoSuchContent", "collection", 42),
+            Err(crate::Error::Sql(_))
+        ));
     }
 }
@@ -0,0 +1,263 @@
+/*
+ This Source Code Form is subject to the terms of the Mozilla Public
+ License, v. 2.0. If a copy of the MPL was not distributed with this
+ file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+
+use md5::{Digest, Md5};
+
+use crate::catalog::Catalog;
+use crate::folders::PathStrategy;
+use crate::images::Image;
+use crate::libraryfiles::LibraryFile;
+use crate::lrobject::{LrId, LrObject};
+
+/// Why `LibraryFile`s are considered duplicates.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum DuplicateKey {
+    /// Same MD5 digest stored in the catalog.
+    Md5(String),
+    /// Same import hash.
+    ImportHash(String),
+    /// Same original file name, capture time and size on disk. The
+    /// size is `None` if sizes aren't compared, see
+    /// `DuplicateFinder::file_size()`.
+    NameTimeSize {
+        original_filename: String,
+        capture_time: String,
+        size: Option<u64>,
+    },
+    /// Same MD5 digest of the content of the file on disk.
+    ContentHash(String),
+}
+
+/// A group of `LibraryFile` that are the same photo.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuplicateGroup {
+    /// Why the files are duplicates.
+    pub key: DuplicateKey,
+    /// The id of the `LibraryFile`s.
+    pub files: Vec<LrId>,
+    /// The id of the file carrying the most metadata.
+    pub best: LrId,
+}
+
+/// Find the `LibraryFile`s catalogued several times. The folders,
+/// library files and images must have been loaded.
+pub struct DuplicateFinder<'a> {
+    catalog: &'a Catalog,
+    file_size: bool,
+    content_hash: bool,
+}
+
+impl<'a> DuplicateFinder<'a> {
+    pub fn new(catalog: &'a Catalog) -> DuplicateFinder<'a> {
+        DuplicateFinder {
+            catalog,
+            file_size: false,
+            content_hash: false,
+        }
+    }
+
+    /// Also compare the size of the files found on disk for
+    /// `DuplicateKey::NameTimeSize`. The files that can't be found
+    /// then don't get that key.
+    pub fn file_size(mut self, file_size: bool) -> DuplicateFinder<'a> {
+        self.file_size = file_size;
+        self
+    }
+
+    /// Also compare the content of the files found on disk. This reads
+    /// all of them.
+    pub fn content_hash(mut self, content_hash: bool) -> DuplicateFinder<'a> {
+        self.content_hash = content_hash;
+        self
+    }
+
+    /// Find the duplicates. A group with the same files as a previous
+    /// one isn't repeated.
+    pub fn find(&self) -> Vec<DuplicateGroup> {
+        let mut images = HashMap::<LrId, Vec<&Image>>::new();
+        for image in self.catalog.images() {
+            images.entry(image.root_file).or_default().push(image);
+        }
+
+        let mut keys = BTreeMap::<DuplicateKey, Vec<LrId>>::new();
+        for file in self.catalog.libfiles() {
+            for key in self.keys(file, images.get(&file.id())) {
+                keys.entry(key).or_default().push(file.id());
+            }
+        }
+
+        let mut seen = HashSet::new();
+        keys.into_iter()
+            .filter(|(_, files)| files.len() > 1)
+            .filter(|(_, files)| seen.insert(files.clone()))
+            .map(|(key, files)| {
+                let best = files
+                    .iter()
+                    .copied()
+                    .max_by_key(|id| {
+                        let score = images
+                            .get(id)
+                            .map(|images| DuplicateFinder::metadata_score(images))
+                            .unwrap_or_default();
+                        // The oldest on a tie.
+                        (score, std::cmp::Reverse(*id))
+                    })
+                    .unwrap_or_default();
+                DuplicateGroup { key, files, best }
+            })
+            .collect()
+    }
+
+    /// The keys for `file`, backing `images`.
+    fn keys(&self, file: &LibraryFile, images: Option<&Vec<&Image>>) -> Vec<DuplicateKey> {
+        let mut keys = vec![];
+        if let Some(ref md5) = file.md5 {
+            keys.push(DuplicateKey::Md5(md5.clone()));
+        }
+        if let Some(ref import_hash) = file.import_hash {
+            keys.push(DuplicateKey::ImportHash(import_hash.clone()));
+        }
+        let original = if file.original_filename.is_empty() {
+            file.file_name()
+        } else {
+            file.original_filename.clone()
+        };
+        // Virtual copies share the file, the master has the capture time.
+        let master =
+            images.and_then(|images| images.iter().find(|image| image.master_image.is_none()));
+        if let Some(image) = master {
+            let size = if self.file_size {
+                self.catalog.library_file_ref(file).size()
+            } else {
+                None
+            };
+            if size.is_some() || !self.file_size {
+                keys.push(DuplicateKey::NameTimeSize {
+                    original_filename: original,
+                    capture_time: image.capture_time.clone(),
+                    size,
+                });
+            }
+        }
+        if self.content_hash {
+            if let Some(hash) = self
+                .catalog
+                .library_file_ref(file)
+                .locate()
+                .filter(|located| located.strategy != PathStrategy::NotFound)
+                .and_then(|located| hash_file(&located.path).ok())
+            {
+                keys.push(DuplicateKey::ContentHash(hash));
+            }
+        }
+        keys
+    }
+
+    /// How much metadata `images` carry.
+    pub fn metadata_score(images: &[&Image]) -> usize {
+        images
+            .iter()
+            .map(|image| {
+                1 + usize::from(image.rating.unwrap_or_default() > 0)
+                    + usize::from(image.pick != 0)
+                    + usize::from(image.xmp.is_some())
+                    + usize::from(image.properties.is_some())
+            })
+            .sum()
+    }
+}
+
+/// The MD5 digest of the content of the file at `path`, as hex.
+pub fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Md5::new();
+    let mut buffer = vec![0_u8; 64 * 1024];
+    loop {
+        let len = file.read(&mut buffer)?;
+        if len == 0 {
+            break;
+        }
+        hasher.update(&buffer[..len]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_file, DuplicateFinder, DuplicateKey};
+    use crate::catalog::tests::open_test_catalog;
+    use crate::remap::{PathRemap, RemapRule};
+
+    #[test]
+    fn test_duplicates() {
+        // 24 is 20 imported again, 25 is 21 in another folder.
+        const SQL: &str = "INSERT INTO AgLibraryFile (id_local, id_global, baseName, extension, folder, idx_filename, importHash, md5, originalFilename, sidecarExtensions) VALUES \
+                           (24, 'D1C1D1E1-0000-4000-8000-000000000024', 'IMG_0001-2', 'CR2', 13, 'IMG_0001-2.CR2', '2017-10-01T10:00:00-1', NULL, 'IMG_0001.CR2', ''), \
+                           (25, 'D1C1D1E1-0000-4000-8000-000000000025', 'IMG_0002', 'JPG', 12, 'IMG_0002.JPG', NULL, NULL, 'IMG_0002.JPG', ''); \
+                           INSERT INTO Adobe_images (id_local, id_global, captureTime, fileFormat, pick, rating, rootFile) VALUES \
+                           (60, 'E1C1D1E1-0000-4000-8000-000000000060', '2017-10-01T10:00:00', 'RAW', 0.0, NULL, 24), \
+                           (61, 'E1C1D1E1-0000-4000-8000-000000000061', '2017-10-01T10:00:01', 'JPG', 1.0, 3, 25); \
+                           INSERT INTO Adobe_images (id_local, id_global, captureTime, fileFormat, masterImage, rootFile) VALUES \
+                           (59, 'E1C1D1E1-0000-4000-8000-000000000059', '2017-10-02T10:00:00', 'JPG', 61, 25);";
+        let (dir, mut catalog) = open_test_catalog(SQL);
+        catalog.load_folders().unwrap();
+        catalog.load_library_files().unwrap();
+        catalog.load_images().unwrap();
+
+        let groups = DuplicateFinder::new(&catalog).find();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            groups[0].key,
+            DuplicateKey::ImportHash("2017-10-01T10:00:00-1".to_string())
+        );
+        assert_eq!(groups[0].files, vec![20, 24]);
+        assert_eq!(groups[0].best, 20);
+        assert!(matches!(
+            groups[1].key,
+            DuplicateKey::NameTimeSize { ref original_filename, size: None, .. }
+                if original_filename == "IMG_0002.JPG"
+        ));
+        assert_eq!(groups[1].files, vec![21, 25]);
+        assert_eq!(groups[1].best, 25);
+
+        // Not found on disk.
+        let groups = DuplicateFinder::new(&catalog).file_size(true).find();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].files, vec![20, 24]);
+
+        // On disk, 21 and 25 differ.
+        let mut remap = PathRemap::new();
+        remap.add_rule(RemapRule::new(
+            "/home/user/Pictures",
+            &dir.path().to_string_lossy(),
+        ));
+        catalog.set_path_remap(remap);
+        std::fs::create_dir_all(dir.path().join("2017/10")).unwrap();
+        std::fs::write(dir.path().join("2017/10/IMG_0002.JPG"), "one").unwrap();
+        std::fs::write(dir.path().join("2017/IMG_0002.JPG"), "three").unwrap();
+        std::fs::write(dir.path().join("2017/10/IMG_0001.CR2"), "raw").unwrap();
+        std::fs::write(dir.path().join("2017/10/IMG_0001-2.CR2"), "raw").unwrap();
+        let groups = DuplicateFinder::new(&catalog)
+            .file_size(true)
+            .content_hash(true)
+            .find();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].files, vec![20, 24]);
+
+        assert_eq!(
+            hash_file(&dir.path().join("2017/10/IMG_0001.CR2")).unwrap(),
+            "bdd166af3a63f7be696dd17a218a6ffb"
+        );
+    }
+}
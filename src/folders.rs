@@ -4,12 +4,15 @@
  file, You can obtain one at http://mozilla.org/MPL/2.0/.
 */
 
+use std::path::{Path, PathBuf};
+
 use rusqlite::{Connection, Row};
 
 use crate::catalog::CatalogVersion;
 use crate::content::Content;
 use crate::fromdb::FromDb;
-use crate::lrobject::{LrId, LrObject};
+use crate::lrobject::{LrId, LrObject, ObjectIndex};
+use crate::remap::PathRemap;
 
 /// A folder define the container for `LibraryFiles`
 /// They are all attached to a `RootFolder`
@@ -61,7 +64,8 @@ impl Folder {
             content: None,
         }
     }
-    pub fn read_content(&self, conn: &Connection) -> Content {
+    /// Read the `content` for this folder from the database.
+    pub fn read_content(&self, conn: &Connection) -> crate::Result<Content> {
         Content::from_db(conn, "AgFolderContent", "containingFolder", self.id)
     }
 }
@@ -123,13 +127,39 @@ impl FromDb for RootFolder {
     }
 }
 
+/// How a path was located on disk.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PathStrategy {
+    /// From the absolute path of the `RootFolder`, after remapping.
+    Absolute,
+    /// From the path of the `RootFolder` relative to the catalog.
+    RelativeToCatalog,
+    /// The path wasn't found: the absolute path is returned.
+    NotFound,
+}
+
+/// A path located on disk.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocatedPath {
+    /// The path.
+    pub path: PathBuf,
+    /// How it was found.
+    pub strategy: PathStrategy,
+}
+
 /// Represent all the folders
 #[derive(Clone, Default)]
 pub struct Folders {
     /// The `RootFolder` list
-    pub roots: Vec<RootFolder>,
+    roots: Vec<RootFolder>,
     /// The `Folder` list
-    pub folders: Vec<Folder>,
+    folders: Vec<Folder>,
+    /// Index of `roots`
+    root_index: ObjectIndex,
+    /// Index of `folders`
+    folder_index: ObjectIndex,
+    /// The remapping applied to resolved paths.
+    remap: PathRemap,
 }
 
 impl Folders {
@@ -144,37 +174,135 @@ impl Folders {
 
     /// Add a `Folder`
     pub fn add_folder(&mut self, folder: Folder) {
-        self.folders.push(folder);
+        self.append_folders(vec![folder]);
     }
 
     /// Add a `RootFolder`
     pub fn add_root_folder(&mut self, root_folder: RootFolder) {
-        self.roots.push(root_folder);
+        self.append_root_folders(vec![root_folder]);
     }
 
     /// Append a vector of `Folder`
     pub fn append_folders(&mut self, mut folders: Vec<Folder>) {
+        self.folder_index.extend(&folders, self.folders.len());
         self.folders.append(&mut folders);
     }
 
     /// Append a vector of `RootFolder`
     pub fn append_root_folders(&mut self, mut root_folders: Vec<RootFolder>) {
+        self.root_index.extend(&root_folders, self.roots.len());
         self.roots.append(&mut root_folders);
     }
 
+    /// Set the remapping applied to the resolved paths.
+    pub fn set_remap(&mut self, remap: PathRemap) {
+        self.remap = remap;
+    }
+
+    /// The remapping applied to the resolved paths.
+    pub fn remap(&self) -> &PathRemap {
+        &self.remap
+    }
+
+    /// The `RootFolder` list
+    pub fn roots(&self) -> &[RootFolder] {
+        &self.roots
+    }
+
+    /// The `Folder` list
+    pub fn folders(&self) -> &[Folder] {
+        &self.folders
+    }
+
     /// Return the eventual `RootFolder` with the id.
     pub fn find_root_folder(&self, id: LrId) -> Option<&RootFolder> {
-        self.roots.iter().find(|&root| root.id() == id)
+        self.root_index.get(&self.roots, id)
+    }
+
+    /// Return the eventual `RootFolder` with the uuid.
+    pub fn find_root_folder_by_uuid(&self, uuid: &str) -> Option<&RootFolder> {
+        self.root_index.get_by_uuid(&self.roots, uuid)
+    }
+
+    /// Return the eventual `Folder` with the id.
+    pub fn find_folder(&self, id: LrId) -> Option<&Folder> {
+        self.folder_index.get(&self.folders, id)
     }
 
-    /// Resolve the folder path by providing an absolute path
-    /// This does not check if the path exist but merely combine
-    /// the `RootFolder` absolute_path and the `Folder` relative path
+    /// Return the eventual `Folder` with the uuid.
+    pub fn find_folder_by_uuid(&self, uuid: &str) -> Option<&Folder> {
+        self.folder_index.get_by_uuid(&self.folders, uuid)
+    }
+
+    /// Resolve the folder path as stored in the catalog: combine the
+    /// `RootFolder` absolute_path and the `Folder` relative path, then
+    /// apply the remapping. This does not check if the path exist: the
+    /// path resolved on disk is given by `locate_folder()`, that also
+    /// tries the path relative to the catalog.
     pub fn resolve_folder_path(&self, folder: &Folder) -> Option<String> {
         let root_folder = self.find_root_folder(folder.root_folder)?;
-        let mut root_path = root_folder.absolute_path.clone();
-        root_path += &folder.path_from_root;
-        Some(root_path)
+        let mut path = root_folder.absolute_path.clone();
+        if !path.is_empty() && !path.ends_with(['/', '\\']) && !folder.path_from_root.is_empty() {
+            path.push('/');
+        }
+        path += folder.path_from_root.trim_start_matches('/');
+        Some(self.remap.remap(&path))
+    }
+
+    /// Return the path of the folder as stored in the catalog, from
+    /// `resolve_folder_path()`.
+    pub fn folder_path(&self, folder: &Folder) -> Option<PathBuf> {
+        self.resolve_folder_path(folder).map(PathBuf::from)
+    }
+
+    /// Return the possible paths of `folder`, in order of preference:
+    /// the one from `folder_path()`, then the one relative to the
+    /// catalog in `catalog_dir`, if the `RootFolder` has one.
+    pub fn folder_candidates(
+        &self,
+        folder: &Folder,
+        catalog_dir: &Path,
+    ) -> Vec<(PathBuf, PathStrategy)> {
+        let mut candidates = vec![];
+        if let Some(path) = self.folder_path(folder) {
+            candidates.push((path, PathStrategy::Absolute));
+        }
+        if let Some(relative) = self
+            .find_root_folder(folder.root_folder)
+            .and_then(|root| root.relative_path_from_catalog.as_ref())
+        {
+            let path = catalog_dir
+                .join(PathRemap::normalize(relative))
+                .join(folder.path_from_root.trim_start_matches('/'));
+            candidates.push((path, PathStrategy::RelativeToCatalog));
+        }
+        candidates
+    }
+
+    /// Locate `folder` on disk, trying the paths from
+    /// `folder_candidates()`.
+    pub fn locate_folder(&self, folder: &Folder, catalog_dir: &Path) -> Option<LocatedPath> {
+        Folders::locate(self.folder_candidates(folder, catalog_dir), |path| {
+            path.to_path_buf()
+        })
+    }
+
+    /// Return the first of `candidates` that exists once `f` is
+    /// applied, or the first one with `PathStrategy::NotFound`.
+    pub(crate) fn locate<F>(candidates: Vec<(PathBuf, PathStrategy)>, f: F) -> Option<LocatedPath>
+    where
+        F: Fn(&Path) -> PathBuf,
+    {
+        candidates
+            .iter()
+            .map(|(path, strategy)| (f(path), *strategy))
+            .find(|(path, _)| path.exists())
+            .or_else(|| {
+                candidates
+                    .first()
+                    .map(|(path, _)| (f(path), PathStrategy::NotFound))
+            })
+            .map(|(path, strategy)| LocatedPath { path, strategy })
     }
 }
 
@@ -193,8 +321,44 @@ fn test_resolve_folder_path() {
     folder.path_from_root = String::from("/2017/10");
     folders.add_folder(folder);
 
-    let resolved = folders.resolve_folder_path(&folders.folders[0]);
+    let resolved = folders.resolve_folder_path(&folders.folders()[0]);
     assert!(resolved.is_some());
     let resolved = resolved.unwrap();
     assert_eq!(resolved, "/home/hub/Pictures/2017/10");
+
+    let path = folders.folder_path(&folders.folders()[0]);
+    assert_eq!(
+        path,
+        Some(std::path::PathBuf::from("/home/hub/Pictures/2017/10"))
+    );
+
+    let mut remap = PathRemap::new();
+    remap.add_rule(crate::RemapRule::new("/home/hub", "/mnt/nas/hub"));
+    folders.set_remap(remap);
+    assert_eq!(
+        folders
+            .resolve_folder_path(&folders.folders()[0])
+            .as_deref(),
+        Some("/mnt/nas/hub/Pictures/2017/10")
+    );
+    assert_eq!(
+        folders.folder_path(&folders.folders()[0]),
+        Some(std::path::PathBuf::from("/mnt/nas/hub/Pictures/2017/10"))
+    );
+
+    // Same path whatever the separators.
+    let mut folder = Folder::new(43, "barfoo");
+    folder.root_folder = 24;
+    folder.path_from_root = String::from("2017/11/");
+    folders.add_folder(folder);
+    assert_eq!(
+        folders
+            .resolve_folder_path(&folders.folders()[1])
+            .as_deref(),
+        Some("/mnt/nas/hub/Pictures/2017/11/")
+    );
+    assert_eq!(
+        folders.folder_path(&folders.folders()[1]),
+        Some(std::path::PathBuf::from("/mnt/nas/hub/Pictures/2017/11/"))
+    );
 }
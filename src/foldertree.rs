@@ -0,0 +1,188 @@
+/*
+ This Source Code Form is subject to the terms of the Mozilla Public
+ License, v. 2.0. If a copy of the MPL was not distributed with this
+ file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+use std::collections::HashMap;
+
+use super::folders::Folders;
+use super::images::Image;
+use super::libraryfiles::LibraryFile;
+use super::lrobject::{LrId, LrObject};
+
+/// Folder tree, like the Lightroom folder panel.
+/// Operate as a hash multimap of parent -> `Vec<child>`, the
+/// `RootFolder` being the top level. The parent of a `Folder` is
+/// found from its `path_from_root`. The `Folder` with an empty
+/// `path_from_root` is the `RootFolder` itself and is merged into it.
+#[derive(Default)]
+pub struct FolderTree {
+    // HashMap. Key is the parent id. Values: the children ids.
+    map: HashMap<LrId, Vec<LrId>>,
+    /// The `RootFolder` ids, sorted by name.
+    roots: Vec<LrId>,
+    /// The `Folder` merged into their `RootFolder`.
+    merged: HashMap<LrId, LrId>,
+    /// The image count of each node, not including the children.
+    counts: HashMap<LrId, usize>,
+}
+
+impl FolderTree {
+    pub fn new() -> FolderTree {
+        FolderTree::default()
+    }
+
+    /// Build the tree for `folders`.
+    pub fn from_folders(folders: &Folders) -> FolderTree {
+        let mut tree = FolderTree::new();
+
+        let mut roots = folders.roots().iter().collect::<Vec<_>>();
+        roots.sort_by(|a, b| a.name.cmp(&b.name));
+        tree.roots = roots.iter().map(|root| root.id()).collect();
+
+        let by_path = folders
+            .folders()
+            .iter()
+            .map(|folder| {
+                (
+                    (folder.root_folder, folder.path_from_root.trim_matches('/')),
+                    folder.id(),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut sorted = folders.folders().iter().collect::<Vec<_>>();
+        sorted.sort_by(|a, b| a.path_from_root.cmp(&b.path_from_root));
+        for folder in sorted {
+            let path = folder.path_from_root.trim_matches('/');
+            if path.is_empty() {
+                tree.merged.insert(folder.id(), folder.root_folder);
+                continue;
+            }
+            // The closest existing ancestor, or the root.
+            let mut parent = folder.root_folder;
+            let mut ancestor = path;
+            while let Some((dir, _)) = ancestor.rsplit_once('/') {
+                if let Some(id) = by_path.get(&(folder.root_folder, dir)) {
+                    parent = *id;
+                    break;
+                }
+                ancestor = dir;
+            }
+            tree.map.entry(parent).or_default().push(folder.id());
+        }
+
+        tree
+    }
+
+    /// Count the images in each folder.
+    pub fn count_images(&mut self, images: &[Image], libfiles: &[LibraryFile]) {
+        let file_folders = libfiles
+            .iter()
+            .map(|file| (file.id(), file.folder))
+            .collect::<HashMap<_, _>>();
+        self.counts.clear();
+        for image in images {
+            if let Some(folder) = file_folders.get(&image.root_file) {
+                let node = self.merged.get(folder).unwrap_or(folder);
+                *self.counts.entry(*node).or_default() += 1;
+            }
+        }
+    }
+
+    /// The `RootFolder` ids, sorted by name.
+    pub fn roots(&self) -> &[LrId] {
+        &self.roots
+    }
+
+    /// Get children for folder with `id`
+    pub fn children_for(&self, id: LrId) -> Vec<LrId> {
+        if let Some(children) = self.map.get(&id) {
+            return children.clone();
+        }
+        vec![]
+    }
+
+    /// The number of images directly in the folder with `id`.
+    pub fn image_count(&self, id: LrId) -> usize {
+        self.counts.get(&id).copied().unwrap_or_default()
+    }
+
+    /// The number of images in the folder with `id` and its
+    /// descendants.
+    pub fn total_image_count(&self, id: LrId) -> usize {
+        self.image_count(id)
+            + self
+                .map
+                .get(&id)
+                .map(|children| {
+                    children
+                        .iter()
+                        .map(|child| self.total_image_count(*child))
+                        .sum::<usize>()
+                })
+                .unwrap_or_default()
+    }
+
+    /// Iterate depth first over the tree, from the roots. Yield the
+    /// depth, 0 for the roots, and the id.
+    pub fn iter(&self) -> FolderTreeIter<'_> {
+        FolderTreeIter {
+            tree: self,
+            stack: self.roots.iter().rev().map(|id| (0, *id)).collect(),
+        }
+    }
+}
+
+/// Depth first iterator over a `FolderTree`.
+pub struct FolderTreeIter<'a> {
+    tree: &'a FolderTree,
+    stack: Vec<(usize, LrId)>,
+}
+
+impl Iterator for FolderTreeIter<'_> {
+    type Item = (usize, LrId);
+
+    fn next(&mut self) -> Option<(usize, LrId)> {
+        let (depth, id) = self.stack.pop()?;
+        if let Some(children) = self.tree.map.get(&id) {
+            self.stack
+                .extend(children.iter().rev().map(|child| (depth + 1, *child)));
+        }
+        Some((depth, id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catalog::tests::open_test_catalog;
+
+    #[test]
+    fn test_folder_tree() {
+        // A folder without its parent, and another root.
+        const SQL: &str = "INSERT INTO AgLibraryRootFolder (id_local, id_global, absolutePath, name) \
+                           VALUES (50, 'C1C1D1E1-0000-4000-8000-000000000050', '/media/card/', 'Card'); \
+                           INSERT INTO AgLibraryFolder (id_local, id_global, pathFromRoot, rootFolder) VALUES \
+                           (51, 'C1C1D1E1-0000-4000-8000-000000000051', '2018/05/01/', 10), \
+                           (52, 'C1C1D1E1-0000-4000-8000-000000000052', 'DCIM/', 50);";
+        let (_dir, mut catalog) = open_test_catalog(SQL);
+        let tree = catalog.load_folder_tree().unwrap();
+
+        assert_eq!(tree.roots(), &[50, 10]);
+        assert_eq!(tree.children_for(10), vec![12, 51]);
+        assert_eq!(tree.children_for(12), vec![13]);
+        assert!(tree.children_for(11).is_empty());
+        assert_eq!(
+            tree.iter().collect::<Vec<_>>(),
+            vec![(0, 50), (1, 52), (0, 10), (1, 12), (2, 13), (1, 51)]
+        );
+
+        assert_eq!(tree.image_count(13), 2);
+        assert_eq!(tree.image_count(12), 2);
+        assert_eq!(tree.image_count(10), 0);
+        assert_eq!(tree.total_image_count(10), 4);
+        assert_eq!(tree.total_image_count(12), 4);
+        assert_eq!(tree.total_image_count(50), 0);
+    }
+}
@@ -4,9 +4,148 @@
  file, You can obtain one at http://mozilla.org/MPL/2.0/.
 */
 
-use rusqlite::Row;
+use std::collections::VecDeque;
+
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{Connection, Row};
 
 use crate::catalog::CatalogVersion;
+use crate::lrobject::LrId;
+
+/// The Lightroom timestamps are in seconds since 2001-01-01 UTC, like
+/// Cocoa. This is the Unix time of that date.
+const LR_EPOCH: i64 = 978_307_200;
+
+/// Convert a Lightroom timestamp to a date. `None` if it is out of
+/// range.
+pub(crate) fn lr_timestamp(timestamp: f64) -> Option<DateTime<Utc>> {
+    if !timestamp.is_finite() {
+        return None;
+    }
+    let secs = timestamp.floor();
+    let nanos = ((timestamp - secs) * 1e9) as u32;
+    Utc.timestamp_opt(LR_EPOCH.checked_add(secs as i64)?, nanos)
+        .single()
+}
+
+/// How to handle the rows that fail to load.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LoadMode {
+    /// Fail on the first row that fails to load.
+    Strict,
+    /// Skip the rows that fail to load, and report a `RowError` for
+    /// each of them.
+    #[default]
+    Lenient,
+}
+
+/// A row that failed to load.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowError {
+    /// The table the object is loaded from.
+    pub table: String,
+    /// The `id_local` of the row, if it could be read.
+    pub id_local: Option<LrId>,
+    /// The column that failed, if known.
+    pub column: Option<String>,
+    /// The error message.
+    pub error: String,
+}
+
+impl RowError {
+    /// Create the `RowError` for `error` while reading `row` from
+    /// `table`.
+    pub(crate) fn new(table: &str, row: &Row, error: &crate::Error) -> RowError {
+        use rusqlite::Error::*;
+
+        let column_name = |idx: usize| row.as_ref().column_name(idx).ok().map(str::to_owned);
+        let column = match error {
+            crate::Error::Sql(err) => match err {
+                InvalidColumnType(_, name, _) | InvalidColumnName(name) => Some(name.clone()),
+                FromSqlConversionFailure(idx, _, _)
+                | IntegralValueOutOfRange(idx, _)
+                | InvalidColumnIndex(idx) => column_name(*idx),
+                _ => None,
+            },
+            _ => None,
+        };
+        RowError {
+            table: RowError::main_table(table),
+            id_local: row.get(0).ok(),
+            column,
+            error: error.to_string(),
+        }
+    }
+
+    /// Create the `RowError` for a `warning` on column `idx` of `row`
+    /// from `table`: the object could still be read.
+    pub(crate) fn warning(table: &str, row: &Row, idx: usize, warning: String) -> RowError {
+        RowError {
+            table: RowError::main_table(table),
+            id_local: row.get(0).ok(),
+            column: row.as_ref().column_name(idx).ok().map(str::to_owned),
+            error: warning,
+        }
+    }
+
+    /// Create the `RowError` for `error` while reading `table`, not
+    /// related to a row.
+    pub(crate) fn for_table(table: &str, error: &crate::Error) -> RowError {
+        RowError {
+            table: RowError::main_table(table),
+            id_local: None,
+            column: None,
+            error: error.to_string(),
+        }
+    }
+
+    /// Only keep the main table of the `FROM` clause `table`.
+    fn main_table(table: &str) -> String {
+        table
+            .split([',', ' '])
+            .next()
+            .unwrap_or_default()
+            .to_owned()
+    }
+}
+
+impl std::fmt::Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.table)?;
+        if let Some(id) = self.id_local {
+            write!(f, "[{id}]")?;
+        }
+        if let Some(ref column) = self.column {
+            write!(f, ".{column}")?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
+/// The objects returned by the `Catalog::load_*()` methods, with the
+/// rows that failed to load. It derefs to the objects.
+#[derive(Debug)]
+pub struct Loaded<'a, T: ?Sized> {
+    /// The objects.
+    pub objects: &'a T,
+    /// The rows that were skipped in `LoadMode::Lenient`. Empty if
+    /// the objects were already loaded by a previous call.
+    pub errors: Vec<RowError>,
+}
+
+impl<'a, T: ?Sized> Loaded<'a, T> {
+    pub(crate) fn new(objects: &'a T, errors: Vec<RowError>) -> Self {
+        Loaded { objects, errors }
+    }
+}
+
+impl<T: ?Sized> std::ops::Deref for Loaded<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.objects
+    }
+}
 
 /// Trait to define loading from a database.
 pub trait FromDb: Sized {
@@ -14,6 +153,15 @@ pub trait FromDb: Sized {
     /// build with the tables and columns provided.
     /// The version of the catalog allow selecting the proper variant.
     fn read_from(version: CatalogVersion, row: &Row) -> crate::Result<Self>;
+    /// Like `read_from()`, also returning the warnings for the values
+    /// that were only partially read, with the index of their
+    /// column. Default is no warnings.
+    fn read_from_with_warnings(
+        version: CatalogVersion,
+        row: &Row,
+    ) -> crate::Result<(Self, Vec<(usize, String)>)> {
+        Self::read_from(version, row).map(|object| (object, vec![]))
+    }
     /// DB tables used in select query.
     fn read_db_tables(version: CatalogVersion) -> &'static str;
     /// DB columns used in select query.
@@ -23,4 +171,129 @@ pub trait FromDb: Sized {
     fn read_join_where(_version: CatalogVersion) -> &'static str {
         ""
     }
+    /// The id column, as it appears in the query. It must also be the
+    /// first column.
+    /// Default is `id_local`.
+    fn read_db_id_column(_version: CatalogVersion) -> &'static str {
+        "id_local"
+    }
+}
+
+/// Build the SELECT query for `T`. `condition` is added to the WHERE
+/// clause if not empty.
+pub(crate) fn select_query<T: FromDb>(version: CatalogVersion, condition: &str) -> String {
+    let mut query = format!(
+        "SELECT {} FROM {}",
+        T::read_db_columns(version),
+        T::read_db_tables(version)
+    );
+    let where_join = T::read_join_where(version);
+    match (where_join.is_empty(), condition.is_empty()) {
+        (false, false) => query += &format!(" WHERE {where_join} AND {condition}"),
+        (false, true) => query += &format!(" WHERE {where_join}"),
+        (true, false) => query += &format!(" WHERE {condition}"),
+        (true, true) => {}
+    }
+    query
+}
+
+/// Iterator over the objects of a table, reading the rows lazily
+/// without caching them. The rows are read by pages, ordered by id.
+///
+/// Rows that fail to load yield an `Error::Row`. Row warnings aren't
+/// reported.
+pub struct ObjectIter<'a, T> {
+    conn: &'a Connection,
+    version: CatalogVersion,
+    /// The query for a page.
+    query: String,
+    /// The number of rows per page.
+    page_size: usize,
+    /// The last id read.
+    last_id: Option<LrId>,
+    /// The current page.
+    page: VecDeque<crate::Result<T>>,
+    /// No more pages.
+    done: bool,
+}
+
+impl<'a, T: FromDb> ObjectIter<'a, T> {
+    /// Default number of rows per page.
+    const PAGE_SIZE: usize = 1000;
+
+    pub(crate) fn new(conn: &'a Connection, version: CatalogVersion) -> ObjectIter<'a, T> {
+        let id = T::read_db_id_column(version);
+        let mut query = select_query::<T>(version, &format!("(?1 IS NULL OR {id} > ?1)"));
+        query += &format!(" ORDER BY {id} LIMIT ?2");
+        ObjectIter {
+            conn,
+            version,
+            query,
+            page_size: Self::PAGE_SIZE,
+            last_id: None,
+            page: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Set the number of rows read at once.
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Load the next page.
+    fn fetch_page(&mut self) -> crate::Result<()> {
+        let mut stmt = self.conn.prepare_cached(&self.query)?;
+        let mut rows = stmt.query(rusqlite::params![self.last_id, self.page_size as i64])?;
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            count += 1;
+            self.last_id = Some(row.get(0)?);
+            match T::read_from(self.version, row) {
+                Ok(object) => self.page.push_back(Ok(object)),
+                Err(crate::Error::Skip) => {}
+                Err(err) => {
+                    let table = T::read_db_tables(self.version);
+                    self.page
+                        .push_back(Err(crate::Error::Row(RowError::new(table, row, &err))));
+                }
+            }
+        }
+        self.done = count < self.page_size;
+        Ok(())
+    }
+}
+
+impl<T: FromDb> Iterator for ObjectIter<'_, T> {
+    type Item = crate::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // A page may contain only skipped rows.
+        while self.page.is_empty() && !self.done {
+            if let Err(err) = self.fetch_page() {
+                self.done = true;
+                return Some(Err(err));
+            }
+        }
+        self.page.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lr_timestamp;
+
+    #[test]
+    fn test_lr_timestamp() {
+        assert_eq!(
+            lr_timestamp(530_000_000.5).map(|date| date.to_rfc3339()),
+            Some("2017-10-18T06:13:20.500+00:00".to_string())
+        );
+        assert!(lr_timestamp(f64::NAN).is_none());
+        assert!(lr_timestamp(f64::INFINITY).is_none());
+        assert!(lr_timestamp(f64::NEG_INFINITY).is_none());
+        assert!(lr_timestamp(1e300).is_none());
+        assert!(lr_timestamp(-1e300).is_none());
+    }
 }
@@ -0,0 +1,155 @@
+/*
+ This Source Code Form is subject to the terms of the Mozilla Public
+ License, v. 2.0. If a copy of the MPL was not distributed with this
+ file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::lrobject::LrId;
+
+/// The path of an object in a hierarchy, like keywords and
+/// collections, from the top level to the object itself.
+///
+/// It is stored as a string like `/11/242`: each component is the id
+/// prefixed with its number of digits, and starts with a `/`.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Genealogy(Vec<LrId>);
+
+impl Genealogy {
+    pub fn new(ids: Vec<LrId>) -> Genealogy {
+        Genealogy(ids)
+    }
+
+    /// Parse the genealogy string `s`.
+    pub fn parse(s: &str) -> crate::Result<Genealogy> {
+        let invalid = || crate::Error::InvalidGenealogy(s.to_string());
+        let path = s.strip_prefix('/').ok_or_else(invalid)?;
+        path.split('/')
+            .map(|component| Genealogy::parse_component(component).ok_or_else(invalid))
+            .collect::<crate::Result<Vec<_>>>()
+            .map(Genealogy)
+    }
+
+    /// Parse the genealogy column `value`. Return the genealogy, and
+    /// `value` if it couldn't be parsed.
+    pub(crate) fn from_column(value: Option<String>) -> (Option<Genealogy>, Option<String>) {
+        match value.as_deref().map(Genealogy::parse) {
+            Some(Ok(genealogy)) => (Some(genealogy), None),
+            Some(Err(_)) => (None, value),
+            None => (None, None),
+        }
+    }
+
+    /// Parse a component: the length of the id, then the id.
+    fn parse_component(component: &str) -> Option<LrId> {
+        // Only digits, so that it can be split at any byte.
+        if !component.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        (1..component.len()).find_map(|prefix| {
+            let len = component[..prefix].parse::<usize>().ok()?;
+            if prefix + len != component.len() {
+                return None;
+            }
+            component[prefix..].parse().ok()
+        })
+    }
+
+    /// All the ids, from the top level to the object.
+    pub fn ids(&self) -> &[LrId] {
+        &self.0
+    }
+
+    /// The id of the object.
+    pub fn id(&self) -> Option<LrId> {
+        self.0.last().copied()
+    }
+
+    /// The ids of the ancestors, from the top level.
+    pub fn ancestors(&self) -> &[LrId] {
+        match self.0.split_last() {
+            Some((_, ancestors)) => ancestors,
+            None => &[],
+        }
+    }
+
+    /// The id of the parent. `None` for the top level.
+    pub fn parent(&self) -> Option<LrId> {
+        self.ancestors().last().copied()
+    }
+
+    /// The depth in the hierarchy, 1 for the top level.
+    pub fn depth(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The genealogy of the child `id`.
+    pub fn child(&self, id: LrId) -> Genealogy {
+        let mut ids = self.0.clone();
+        ids.push(id);
+        Genealogy(ids)
+    }
+
+    /// Whether it matches the object `id` with `parent`. A parent of
+    /// 0 is the top level.
+    pub fn is_consistent(&self, id: LrId, parent: LrId) -> bool {
+        self.id() == Some(id) && self.parent().unwrap_or(0) == parent
+    }
+}
+
+impl fmt::Display for Genealogy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for id in &self.0 {
+            let id = id.to_string();
+            write!(f, "/{}{id}", id.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Genealogy {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Genealogy> {
+        Genealogy::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Genealogy;
+
+    #[test]
+    fn test_genealogy() {
+        let genealogy = Genealogy::parse("/11/242/3123").unwrap();
+        assert_eq!(genealogy.ids(), &[1, 42, 123]);
+        assert_eq!(genealogy.id(), Some(123));
+        assert_eq!(genealogy.ancestors(), &[1, 42]);
+        assert_eq!(genealogy.parent(), Some(42));
+        assert_eq!(genealogy.depth(), 3);
+        assert_eq!(genealogy.to_string(), "/11/242/3123");
+        assert!(genealogy.is_consistent(123, 42));
+        assert!(!genealogy.is_consistent(123, 1));
+
+        let genealogy = "/101234567890".parse::<Genealogy>().unwrap();
+        assert_eq!(genealogy.ids(), &[1234567890]);
+        assert_eq!(genealogy.parent(), None);
+        assert!(genealogy.is_consistent(1234567890, 0));
+        assert_eq!(genealogy.child(5).to_string(), "/101234567890/15");
+
+        for invalid in [
+            "", "/", "11/12", "/1", "/312", "/21a", "/11//12", "/1é", "/2é", "/é1", "/11/1é",
+        ] {
+            assert!(
+                matches!(
+                    Genealogy::parse(invalid),
+                    Err(crate::Error::InvalidGenealogy(ref s)) if s == invalid
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,217 @@
+/*
+ This Source Code Form is subject to the terms of the Mozilla Public
+ License, v. 2.0. If a copy of the MPL was not distributed with this
+ file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use crate::catalog::Catalog;
+use crate::folders::{Folder, Folders, LocatedPath, PathStrategy, RootFolder};
+use crate::images::Image;
+use crate::libraryfiles::{LibraryFile, Sidecar};
+
+/// An `Image` in its `Catalog`, allowing to navigate to its
+/// `LibraryFile`. Obtained with `Catalog::image_ref()`.
+#[derive(Clone, Copy)]
+pub struct ImageRef<'a> {
+    catalog: &'a Catalog,
+    image: &'a Image,
+}
+
+impl<'a> ImageRef<'a> {
+    pub(crate) fn new(catalog: &'a Catalog, image: &'a Image) -> ImageRef<'a> {
+        ImageRef { catalog, image }
+    }
+
+    /// The `Image`
+    pub fn image(&self) -> &'a Image {
+        self.image
+    }
+
+    /// The `LibraryFile` backing the image.
+    pub fn file(&self) -> Option<FileRef<'a>> {
+        self.catalog
+            .library_file(self.image.root_file)
+            .map(|file| FileRef::new(self.catalog, file))
+    }
+
+    /// The path of the image file as stored in the catalog, after
+    /// remapping. Use `locate()` for the path on disk.
+    pub fn path(&self) -> Option<PathBuf> {
+        self.file()?.path()
+    }
+
+    /// Locate the image file on disk.
+    pub fn locate(&self) -> Option<LocatedPath> {
+        self.file()?.locate()
+    }
+
+    /// The files on disk belonging to the image: the file and its
+    /// sidecars that can be found.
+    pub fn files_on_disk(&self) -> Vec<PathBuf> {
+        self.file()
+            .map(|file| file.files_on_disk())
+            .unwrap_or_default()
+    }
+}
+
+impl Deref for ImageRef<'_> {
+    type Target = Image;
+
+    fn deref(&self) -> &Image {
+        self.image
+    }
+}
+
+/// A `LibraryFile` in its `Catalog`, allowing to navigate to its
+/// `Folder`.
+#[derive(Clone, Copy)]
+pub struct FileRef<'a> {
+    catalog: &'a Catalog,
+    file: &'a LibraryFile,
+}
+
+impl<'a> FileRef<'a> {
+    pub(crate) fn new(catalog: &'a Catalog, file: &'a LibraryFile) -> FileRef<'a> {
+        FileRef { catalog, file }
+    }
+
+    /// The `LibraryFile`
+    pub fn library_file(&self) -> &'a LibraryFile {
+        self.file
+    }
+
+    /// The `Folder` containing the file.
+    pub fn folder(&self) -> Option<FolderRef<'a>> {
+        self.catalog
+            .folder(self.file.folder)
+            .map(|folder| FolderRef::new(self.catalog, folder))
+    }
+
+    /// The path of the file as stored in the catalog, after remapping.
+    /// Use `locate()` for the path on disk.
+    pub fn path(&self) -> Option<PathBuf> {
+        Some(self.folder()?.path()?.join(self.file.file_name()))
+    }
+
+    /// Locate the file on disk. See `Folders::folder_candidates()`.
+    pub fn locate(&self) -> Option<LocatedPath> {
+        let folder = self.folder()?;
+        let name = self.file.file_name();
+        Folders::locate(folder.candidates(), |path| path.join(&name))
+    }
+
+    /// The size of the file on disk, as the catalog doesn't store it.
+    /// `None` if the file can't be found.
+    pub fn size(&self) -> Option<u64> {
+        let located = self.locate()?;
+        std::fs::metadata(located.path)
+            .ok()
+            .map(|metadata| metadata.len())
+    }
+
+    /// The sidecars, with their path in the folder located on disk.
+    /// See `FolderRef::locate()`.
+    pub fn sidecars(&self) -> Vec<Sidecar> {
+        let path = self
+            .folder()
+            .and_then(|folder| folder.locate())
+            .map(|located| located.path);
+        self.file
+            .sidecars()
+            .into_iter()
+            .map(|mut sidecar| {
+                sidecar.path = path
+                    .as_ref()
+                    .map(|path| path.join(self.file.sidecar_file_name(&sidecar)));
+                sidecar
+            })
+            .collect()
+    }
+
+    /// The paths of the sidecar files.
+    pub fn sidecar_paths(&self) -> Vec<PathBuf> {
+        self.sidecars()
+            .into_iter()
+            .filter_map(|sidecar| sidecar.path)
+            .collect()
+    }
+
+    /// The file and its sidecars that can be found on disk. See
+    /// `Folders::folder_candidates()`.
+    pub fn files_on_disk(&self) -> Vec<PathBuf> {
+        let folder = match self.folder() {
+            Some(folder) => folder,
+            None => return vec![],
+        };
+        let candidates = folder.candidates();
+        std::iter::once(self.file.file_name())
+            .chain(self.file.sidecar_file_names())
+            .filter_map(|name| {
+                Folders::locate(candidates.clone(), |path| path.join(&name))
+                    .filter(|located| located.strategy != PathStrategy::NotFound)
+                    .map(|located| located.path)
+            })
+            .collect()
+    }
+}
+
+impl Deref for FileRef<'_> {
+    type Target = LibraryFile;
+
+    fn deref(&self) -> &LibraryFile {
+        self.file
+    }
+}
+
+/// A `Folder` in its `Catalog`, allowing to navigate to its
+/// `RootFolder`.
+#[derive(Clone, Copy)]
+pub struct FolderRef<'a> {
+    catalog: &'a Catalog,
+    folder: &'a Folder,
+}
+
+impl<'a> FolderRef<'a> {
+    pub(crate) fn new(catalog: &'a Catalog, folder: &'a Folder) -> FolderRef<'a> {
+        FolderRef { catalog, folder }
+    }
+
+    /// The `Folder`
+    pub fn folder(&self) -> &'a Folder {
+        self.folder
+    }
+
+    /// The `RootFolder` of the folder.
+    pub fn root(&self) -> Option<&'a RootFolder> {
+        self.catalog.root_folder(self.folder.root_folder)
+    }
+
+    /// The path of the folder as stored in the catalog, from
+    /// `Folders::folder_path()`. Use `locate()` for the path on disk.
+    pub fn path(&self) -> Option<PathBuf> {
+        self.catalog.folders().folder_path(self.folder)
+    }
+
+    /// The possible paths of the folder.
+    fn candidates(&self) -> Vec<(PathBuf, PathStrategy)> {
+        self.catalog
+            .folders()
+            .folder_candidates(self.folder, &self.catalog.directory())
+    }
+
+    /// Locate the folder on disk. See `Folders::folder_candidates()`.
+    pub fn locate(&self) -> Option<LocatedPath> {
+        Folders::locate(self.candidates(), Path::to_path_buf)
+    }
+}
+
+impl Deref for FolderRef<'_> {
+    type Target = Folder;
+
+    fn deref(&self) -> &Folder {
+        self.folder
+    }
+}
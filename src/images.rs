@@ -25,14 +25,14 @@ pub struct Properties {
 
 impl Properties {
     #[allow(clippy::unnecessary_unwrap)]
-    fn loupe_focus(value: &[lron::Object]) -> Option<Point> {
-        use crate::lron::{Object, Value};
+    fn loupe_focus(value: &[lron::Value]) -> Option<Point> {
+        use crate::lron::Value;
 
         let mut x: Option<f64> = None;
         let mut y: Option<f64> = None;
         let mut is_point = false;
         value.iter().for_each(|o| {
-            if let Object::Pair(p) = o {
+            if let Value::Pair(p) = o {
                 match p.key.as_str() {
                     "_ag_className" => is_point = p.value == Value::Str("AgPoint".to_owned()),
                     "y" => y = p.value.to_number(),
@@ -53,8 +53,8 @@ impl Properties {
     }
 
     #[allow(clippy::unnecessary_unwrap)]
-    fn properties_from(value: &[lron::Object]) -> Self {
-        use crate::lron::{Object, Value};
+    fn properties_from(value: &[lron::Value]) -> Self {
+        use crate::lron::Value;
 
         let mut props = Properties::default();
         let mut crop_aspect_h: Option<i32> = None;
@@ -65,7 +65,7 @@ impl Properties {
         let mut left: Option<f64> = None;
         let mut right: Option<f64> = None;
         value.iter().for_each(|o| {
-            if let Object::Pair(p) = o {
+            if let Value::Pair(p) = o {
                 match p.key.as_str() {
                     "loupeFocusPoint" => {
                         if let Value::Dict(ref v) = p.value {
@@ -119,12 +119,12 @@ impl Properties {
     }
 }
 
-impl From<lron::Object> for Properties {
-    fn from(object: lron::Object) -> Self {
-        use crate::lron::{Object, Value};
+impl From<lron::Value> for Properties {
+    fn from(value: lron::Value) -> Self {
+        use crate::lron::Value;
 
-        match object {
-            Object::Pair(ref s) => {
+        match value {
+            Value::Pair(ref s) => {
                 if &s.key == "properties" {
                     match s.value {
                         Value::Dict(ref dict) => Self::properties_from(dict),
@@ -160,16 +160,18 @@ pub struct Image {
     pub orientation: Option<String>,
     /// Capture date.
     pub capture_time: String,
-    /// XMP block as stored in the database. If len() == 0,
-    /// there is no XMP.
-    pub xmp: String,
+    /// XMP block as stored in the database. `None` if the image has
+    /// no Adobe_AdditionalMetadata row.
+    pub xmp: Option<String>,
     /// XMP is embedded: whether the XMP packet in the file
     /// like a JPEG, or in a sidecar like in a RAW (non DNG)
-    /// file, regardless of `xmp`.
-    pub xmp_embedded: bool,
+    /// file, regardless of `xmp`. `None` if unknown.
+    pub xmp_embedded: Option<bool>,
     /// The external XMP (ie not in the database) is different.
-    pub xmp_external_dirty: bool,
-    /// Misc properties from the Adobe_imageProperties table
+    /// `None` if unknown.
+    pub xmp_external_dirty: Option<bool>,
+    /// Misc properties from the Adobe_imageProperties table. `None`
+    /// if there is no row or it couldn't be parsed.
     pub properties: Option<Properties>,
 }
 
@@ -199,13 +201,35 @@ impl LrObject for Image {
 }
 
 impl FromDb for Image {
-    fn read_from(_version: CatalogVersion, row: &Row) -> crate::Result<Self> {
-        let properties = row
-            .get::<usize, String>(13)
-            .ok()
-            .and_then(|v| lron::Object::from_string(&v).ok())
-            .map(Properties::from);
-        Ok(Image {
+    fn read_from(version: CatalogVersion, row: &Row) -> crate::Result<Self> {
+        Self::read_from_with_warnings(version, row).map(|(image, _)| image)
+    }
+    fn read_from_with_warnings(
+        _version: CatalogVersion,
+        row: &Row,
+    ) -> crate::Result<(Self, Vec<(usize, String)>)> {
+        const PROPERTIES: usize = 13;
+
+        // Parse leniently so that an odd value doesn't lose all the
+        // properties. Report what was skipped.
+        let mut warnings = vec![];
+        let properties = row.get::<usize, String>(PROPERTIES).ok().and_then(|v| {
+            match lron::Value::from_string_lenient(&v) {
+                Ok((value, diagnostics)) => {
+                    warnings.extend(
+                        diagnostics
+                            .iter()
+                            .map(|diagnostic| (PROPERTIES, diagnostic.to_string())),
+                    );
+                    Some(Properties::from(value))
+                }
+                Err(err) => {
+                    warnings.push((PROPERTIES, err.to_string()));
+                    None
+                }
+            }
+        });
+        let image = Image {
             id: row.get(0)?,
             uuid: row.get(1)?,
             master_image: row.get(2).ok(),
@@ -220,16 +244,20 @@ impl FromDb for Image {
             xmp_embedded: row.get(11)?,
             xmp_external_dirty: row.get(12)?,
             properties,
-        })
+        };
+        Ok((image, warnings))
     }
     fn read_db_tables(_version: CatalogVersion) -> &'static str {
-        "Adobe_images as img,Adobe_AdditionalMetadata as meta,Adobe_imageProperties as props"
+        // Outer join as the metadata and properties rows may be missing.
+        "Adobe_images as img \
+         LEFT JOIN Adobe_AdditionalMetadata as meta ON meta.image = img.id_local \
+         LEFT JOIN Adobe_imageProperties as props ON props.image = img.id_local"
     }
     fn read_db_columns(_version: CatalogVersion) -> &'static str {
         "img.id_local,img.id_global,img.masterImage,img.rating,img.rootFile,img.fileFormat,cast(img.pick as integer) as pick,img.orientation,img.captureTime,img.copyName,meta.xmp,meta.embeddedXmp,meta.externalXmpIsDirty,props.propertiesString"
     }
-    fn read_join_where(_version: CatalogVersion) -> &'static str {
-        "meta.image = img.id_local and props.image = img.id_local"
+    fn read_db_id_column(_version: CatalogVersion) -> &'static str {
+        "img.id_local"
     }
 }
 
@@ -252,9 +280,9 @@ mod tests {
             orientation: None,
             capture_time: String::new(),
             copy_name: None,
-            xmp: String::new(),
-            xmp_embedded: false,
-            xmp_external_dirty: false,
+            xmp: None,
+            xmp_embedded: None,
+            xmp_external_dirty: None,
             properties: None,
         };
 
@@ -288,11 +316,11 @@ mod tests {
 	}, \
         }";
 
-        let object = lron::Object::from_string(LRON1);
+        let value = lron::Value::from_string(LRON1);
 
-        assert!(object.is_ok());
-        let object = object.unwrap();
-        let properties = Properties::from(object);
+        assert!(value.is_ok());
+        let value = value.unwrap();
+        let properties = Properties::from(value);
 
         assert!(properties.loupe_focus.is_some());
         if let Some(ref loupe_focus) = properties.loupe_focus {
@@ -0,0 +1,86 @@
+/*
+ This Source Code Form is subject to the terms of the Mozilla Public
+ License, v. 2.0. If a copy of the MPL was not distributed with this
+ file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+use rusqlite::Row;
+
+use crate::catalog::CatalogVersion;
+use crate::fromdb::{lr_timestamp, FromDb};
+use crate::lrobject::LrId;
+
+/// An import session: a batch of images imported at once.
+pub struct Import {
+    /// Local id of the import
+    id: LrId,
+    /// Name of the import. Usually `None`.
+    pub name: Option<String>,
+    /// Date of the import, like `2017-10-03T09:00:00`.
+    pub date: String,
+    /// The number of images as recorded by Lightroom. `None` if
+    /// unknown.
+    pub image_count: Option<i64>,
+    /// The images imported. Filled by `Catalog::load_imports()`.
+    pub images: Vec<LrId>,
+}
+
+impl FromDb for Import {
+    fn read_from(version: CatalogVersion, row: &Row) -> crate::Result<Self> {
+        match version {
+            CatalogVersion::Lr3 | CatalogVersion::Lr4 | CatalogVersion::Lr6 => Ok(Import {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                date: row.get(2)?,
+                image_count: row.get(3).ok(),
+                images: vec![],
+            }),
+            CatalogVersion::Lr2 => {
+                let tag_type: Box<str> = row.get(4)?;
+                if tag_type.as_ref() != "AgImportTagKind" {
+                    return Err(crate::Error::Skip);
+                }
+                // The tag creation is the import date.
+                let date = row
+                    .get::<usize, f64>(2)
+                    .ok()
+                    .and_then(lr_timestamp)
+                    .map(|date| date.format("%Y-%m-%dT%H:%M:%S").to_string())
+                    .unwrap_or_default();
+                Ok(Import {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    date,
+                    image_count: row.get(3).ok(),
+                    images: vec![],
+                })
+            }
+            _ => Err(crate::Error::UnsupportedVersion),
+        }
+    }
+
+    fn read_db_tables(version: CatalogVersion) -> &'static str {
+        match version {
+            CatalogVersion::Lr3 | CatalogVersion::Lr4 | CatalogVersion::Lr6 => "AgLibraryImport",
+            CatalogVersion::Lr2 => "AgLibraryTag",
+            _ => "",
+        }
+    }
+
+    fn read_db_columns(version: CatalogVersion) -> &'static str {
+        match version {
+            CatalogVersion::Lr3 | CatalogVersion::Lr4 | CatalogVersion::Lr6 => {
+                "id_local,name,importDate,imageCount"
+            }
+            CatalogVersion::Lr2 => "id_local,name,dateCreated,imageCountCache,kindName",
+            _ => "",
+        }
+    }
+}
+
+impl Import {
+    /// Return the local_id of the import.
+    pub fn id(&self) -> LrId {
+        self.id
+    }
+}
@@ -8,6 +8,7 @@ use rusqlite::Row;
 
 use crate::catalog::CatalogVersion;
 use crate::fromdb::FromDb;
+use crate::genealogy::Genealogy;
 use crate::lrobject::{LrId, LrObject};
 
 /// A Lightroom keyword.
@@ -22,6 +23,11 @@ pub struct Keyword {
     pub name: String,
     /// The parent. For top-level the value is `Catalog::root_keyword_id`
     pub parent: LrId,
+    /// The path in the keyword hierarchy. `None` if missing or
+    /// invalid.
+    pub genealogy: Option<Genealogy>,
+    /// The genealogy string if it couldn't be parsed.
+    pub invalid_genealogy: Option<String>,
 }
 
 impl LrObject for Keyword {
@@ -37,11 +43,14 @@ impl FromDb for Keyword {
     fn read_from(_version: CatalogVersion, row: &Row) -> crate::Result<Self> {
         let name = row.get(3).ok();
         let parent = row.get(4).ok();
+        let (genealogy, invalid_genealogy) = Genealogy::from_column(row.get(5).ok());
         Ok(Keyword {
             id: row.get(0)?,
             uuid: row.get(1)?,
             name: name.unwrap_or_default(),
             parent: parent.unwrap_or(0),
+            genealogy,
+            invalid_genealogy,
         })
     }
 
@@ -50,7 +59,7 @@ impl FromDb for Keyword {
     }
 
     fn read_db_columns(_version: CatalogVersion) -> &'static str {
-        "id_local,id_global,cast(dateCreated as text),name,parent"
+        "id_local,id_global,cast(dateCreated as text),name,parent,genealogy"
     }
 }
 
@@ -62,6 +71,20 @@ impl Keyword {
             parent,
             uuid: String::from(uuid),
             name: String::from(name),
+            genealogy: None,
+            invalid_genealogy: None,
         }
     }
+
+    /// Check that the genealogy, if any, matches `parent`. An invalid
+    /// genealogy doesn't.
+    pub fn check_genealogy(&self) -> bool {
+        if self.invalid_genealogy.is_some() {
+            return false;
+        }
+        self.genealogy
+            .as_ref()
+            .map(|genealogy| genealogy.is_consistent(self.id, self.parent))
+            .unwrap_or(true)
+    }
 }
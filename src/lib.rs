@@ -11,14 +11,23 @@ extern crate rusqlite;
 mod catalog;
 mod collections;
 mod content;
+mod duplicates;
 mod folders;
+mod foldertree;
 mod fromdb;
+mod genealogy;
+mod imageref;
 mod images;
+mod imports;
 mod keywords;
 mod keywordtree;
 mod libraryfiles;
 mod lrobject;
 pub mod lron;
+mod reconcile;
+mod relocate;
+mod remap;
+mod shared;
 
 /// Point
 #[derive(Debug, PartialEq)]
@@ -53,12 +62,56 @@ pub enum Error {
     #[error("LrCat: Skip.")]
     /// Skip the item (when reading from Db)
     Skip,
+    #[error("LrCat: Catalog not open.")]
+    /// The catalog hasn't been opened
+    NotOpen,
+    #[error("LrCat: Catalog is in use, found {0}.")]
+    /// The catalog is opened by Lightroom. Contain the path of the
+    /// lock file.
+    CatalogLocked(std::path::PathBuf),
     #[error("LrCat: Unsupported catalog version.")]
     /// Unsupported catalog version
     UnsupportedVersion,
+    #[error("LrCat: Not a SQLite database.")]
+    /// The file isn't a SQLite database.
+    NotSqlite,
+    #[error("LrCat: Not a Lightroom catalog.")]
+    /// The file is a SQLite database but not a Lightroom catalog.
+    NotLightroomCatalog,
+    #[error("LrCat: Catalog is corrupt or encrypted: {0}")]
+    /// The file looks like a SQLite database but can't be read.
+    Corrupt(rusqlite::Error),
+    #[error("LrCat: Invalid remap rule {0:?}, expected FROM=TO.")]
+    /// A path remapping rule couldn't be parsed.
+    InvalidRemapRule(String),
+    #[error("LrCat: Invalid genealogy {0:?}.")]
+    /// The genealogy string couldn't be parsed.
+    InvalidGenealogy(String),
+    #[error("LrCat: Unknown root folder {0}.")]
+    /// There is no `RootFolder` with this id.
+    UnknownRootFolder(LrId),
+    #[error("LrCat: Missing folders: {0:?}.")]
+    /// These folders don't exist.
+    MissingFolders(Vec<std::path::PathBuf>),
+    #[error("LrCat: Invalid path {0:?}.")]
+    /// The path isn't absolute, or isn't valid UTF-8, where it must be.
+    InvalidPath(std::path::PathBuf),
+    #[error("LrCat: Folders not loaded.")]
+    /// The folders must be loaded first.
+    FoldersNotLoaded,
+    #[error("LrCat: Catalog version {0} is too new.")]
+    /// The catalog is from a newer Lightroom. Contain the version
+    /// string.
+    VersionTooNew(String),
     #[error("LrCat: SQL error: {0}")]
     /// Sql Error
     Sql(#[from] rusqlite::Error),
+    #[error("LrCat: Error loading row: {0}")]
+    /// A row failed to load in `LoadMode::Strict`
+    Row(fromdb::RowError),
+    #[error("LrCat: IO error: {0}")]
+    /// IO Error
+    Io(#[from] std::io::Error),
     #[error("LrCat: Lron parsing error: {0}")]
     /// Lron parsing error
     Lron(#[from] peg::error::ParseError<peg::str::LineCol>),
@@ -67,12 +120,22 @@ pub enum Error {
 /// Result type for the crate.
 pub type Result<T> = std::result::Result<T, Error>;
 
-pub use catalog::{Catalog, CatalogVersion};
+pub use catalog::{Catalog, CatalogVersion, LoadProgress, LoadStep};
 pub use collections::Collection;
 pub use content::Content;
-pub use folders::{Folder, Folders, RootFolder};
+pub use duplicates::{DuplicateFinder, DuplicateGroup, DuplicateKey};
+pub use folders::{Folder, Folders, LocatedPath, PathStrategy, RootFolder};
+pub use foldertree::{FolderTree, FolderTreeIter};
+pub use fromdb::{LoadMode, Loaded, ObjectIter, RowError};
+pub use genealogy::Genealogy;
+pub use imageref::{FileRef, FolderRef, ImageRef};
 pub use images::Image;
+pub use imports::Import;
 pub use keywords::Keyword;
 pub use keywordtree::KeywordTree;
-pub use libraryfiles::LibraryFile;
-pub use lrobject::{LrId, LrObject};
+pub use libraryfiles::{LibraryFile, Sidecar, SidecarKind};
+pub use lrobject::{CatalogObject, LrId, LrObject, ObjectIndex};
+pub use reconcile::{FileStatus, ReconcileEntry, ReconcileReport, Reconciler};
+pub use relocate::RootRelocation;
+pub use remap::{PathRemap, RemapRule};
+pub use shared::SharedCatalog;
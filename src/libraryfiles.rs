@@ -4,12 +4,53 @@
  file, You can obtain one at http://mozilla.org/MPL/2.0/.
 */
 
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
 use rusqlite::Row;
 
 use crate::catalog::CatalogVersion;
-use crate::fromdb::FromDb;
+use crate::fromdb::{lr_timestamp, FromDb};
 use crate::lrobject::{LrId, LrObject};
 
+/// The kind of a `Sidecar`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SidecarKind {
+    /// XMP metadata.
+    Xmp,
+    /// JPEG companion of a RAW file, imported as RAW+JPEG.
+    Jpeg,
+    /// Anything else.
+    Other,
+}
+
+/// A sidecar file of a `LibraryFile`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sidecar {
+    /// The kind of sidecar.
+    pub kind: SidecarKind,
+    /// The extension, as is.
+    pub extension: String,
+    /// The path of the sidecar, if resolved.
+    pub path: Option<PathBuf>,
+}
+
+impl Sidecar {
+    /// Create an unresolved sidecar with `extension`.
+    pub fn new(extension: &str) -> Sidecar {
+        let kind = match extension.to_ascii_lowercase().as_str() {
+            "xmp" => SidecarKind::Xmp,
+            "jpg" | "jpeg" => SidecarKind::Jpeg,
+            _ => SidecarKind::Other,
+        };
+        Sidecar {
+            kind,
+            extension: extension.to_string(),
+            path: None,
+        }
+    }
+}
+
 /// Define a backing file in the `Catalog`. `Images` are
 /// connected to one.
 pub struct LibraryFile {
@@ -23,6 +64,16 @@ pub struct LibraryFile {
     pub folder: LrId,
     /// Extensions of the sidecar(s), comma separated.
     pub sidecar_extensions: String,
+    /// The file name at import, before any rename.
+    pub original_filename: String,
+    /// The file name, as indexed.
+    pub idx_filename: String,
+    /// MD5 digest of the file, if computed.
+    pub md5: Option<String>,
+    /// Hash computed at import to detect duplicates.
+    pub import_hash: Option<String>,
+    /// Modification time of the file when last seen.
+    pub external_mod_time: Option<DateTime<Utc>>,
 }
 
 impl LrObject for LibraryFile {
@@ -42,15 +93,98 @@ impl FromDb for LibraryFile {
             basename: row.get(2)?,
             extension: row.get(3)?,
             folder: row.get(4)?,
-            sidecar_extensions: row.get(5)?,
+            sidecar_extensions: row.get::<usize, Option<String>>(5)?.unwrap_or_default(),
+            original_filename: row.get::<usize, Option<String>>(6)?.unwrap_or_default(),
+            idx_filename: row.get::<usize, Option<String>>(7)?.unwrap_or_default(),
+            md5: row.get(8)?,
+            import_hash: row.get(9)?,
+            external_mod_time: row.get::<usize, Option<f64>>(10)?.and_then(lr_timestamp),
         })
     }
     fn read_db_tables(_version: CatalogVersion) -> &'static str {
         "AgLibraryFile"
     }
-    fn read_db_columns(_version: CatalogVersion) -> &'static str {
-        "id_local,id_global,baseName,extension,folder,sidecarExtensions"
+    fn read_db_columns(version: CatalogVersion) -> &'static str {
+        match version {
+            CatalogVersion::Lr4 | CatalogVersion::Lr6 => {
+                "id_local,id_global,baseName,extension,folder,sidecarExtensions,originalFilename,\
+                 idx_filename,md5,importHash,externalModTime"
+            }
+            // The extended attributes aren't known to exist in older
+            // catalogs.
+            _ => {
+                "id_local,id_global,baseName,extension,folder,sidecarExtensions,\
+                 NULL AS originalFilename,NULL AS idx_filename,NULL AS md5,NULL AS importHash,\
+                 NULL AS externalModTime"
+            }
+        }
     }
 }
 
-impl LibraryFile {}
+impl LibraryFile {
+    /// The file name, with the extension.
+    pub fn file_name(&self) -> String {
+        if self.extension.is_empty() {
+            self.basename.clone()
+        } else {
+            format!("{}.{}", self.basename, self.extension)
+        }
+    }
+
+    /// Whether the file was renamed since import.
+    pub fn is_renamed(&self) -> bool {
+        !self.original_filename.is_empty() && self.original_filename != self.file_name()
+    }
+
+    /// The sidecars, unresolved.
+    pub fn sidecars(&self) -> Vec<Sidecar> {
+        self.sidecar_extensions
+            .split(',')
+            .map(str::trim)
+            .filter(|ext| !ext.is_empty())
+            .map(Sidecar::new)
+            .collect()
+    }
+
+    /// The file name of `sidecar`.
+    pub fn sidecar_file_name(&self, sidecar: &Sidecar) -> String {
+        format!("{}.{}", self.basename, sidecar.extension)
+    }
+
+    /// The file names of the sidecars.
+    pub fn sidecar_file_names(&self) -> Vec<String> {
+        self.sidecars()
+            .iter()
+            .map(|sidecar| self.sidecar_file_name(sidecar))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_sidecars() {
+    let file = LibraryFile {
+        id: 1,
+        uuid: String::new(),
+        basename: String::from("IMG_0001"),
+        extension: String::from("CR2"),
+        folder: 0,
+        sidecar_extensions: String::from("JPG,xmp,thm"),
+        original_filename: String::new(),
+        idx_filename: String::new(),
+        md5: None,
+        import_hash: None,
+        external_mod_time: None,
+    };
+    let sidecars = file.sidecars();
+    assert_eq!(
+        sidecars.iter().map(|s| s.kind).collect::<Vec<_>>(),
+        vec![SidecarKind::Jpeg, SidecarKind::Xmp, SidecarKind::Other]
+    );
+    assert_eq!(sidecars[0].extension, "JPG");
+    assert_eq!(sidecars[0].path, None);
+    assert_eq!(
+        file.sidecar_file_names(),
+        vec!["IMG_0001.JPG", "IMG_0001.xmp", "IMG_0001.thm"]
+    );
+}
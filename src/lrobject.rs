@@ -4,6 +4,13 @@
  file, You can obtain one at http://mozilla.org/MPL/2.0/.
 */
 
+use std::collections::HashMap;
+
+use crate::folders::{Folder, RootFolder};
+use crate::images::Image;
+use crate::keywords::Keyword;
+use crate::libraryfiles::LibraryFile;
+
 /// Lightroom local id Used as a catalog globel identifier. Values
 /// seems to be unique accross types.
 pub type LrId = i64;
@@ -17,3 +24,108 @@ pub trait LrObject {
     /// though.
     fn uuid(&self) -> &str;
 }
+
+/// Index of a slice of `LrObject` by id and uuid.
+#[derive(Clone, Debug, Default)]
+pub struct ObjectIndex {
+    /// Position by id
+    ids: HashMap<LrId, usize>,
+    /// Position by uuid
+    uuids: HashMap<String, usize>,
+}
+
+impl ObjectIndex {
+    /// Build the index for `objects`.
+    pub fn new<T: LrObject>(objects: &[T]) -> ObjectIndex {
+        let mut index = ObjectIndex::default();
+        index.extend(objects, 0);
+        index
+    }
+
+    /// Add `objects` to the index, the first one being at `position`
+    /// in the indexed slice.
+    pub fn extend<T: LrObject>(&mut self, objects: &[T], position: usize) {
+        self.ids.reserve(objects.len());
+        self.uuids.reserve(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            self.ids.insert(object.id(), position + i);
+            self.uuids.insert(object.uuid().to_owned(), position + i);
+        }
+    }
+
+    /// Clear the index.
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.uuids.clear();
+    }
+
+    /// Position of the object with `id`.
+    pub fn position(&self, id: LrId) -> Option<usize> {
+        self.ids.get(&id).copied()
+    }
+
+    /// Position of the object with `uuid`.
+    pub fn position_by_uuid(&self, uuid: &str) -> Option<usize> {
+        self.uuids.get(uuid).copied()
+    }
+
+    /// Get the object with `id` from the indexed `objects`.
+    pub fn get<'a, T: LrObject>(&self, objects: &'a [T], id: LrId) -> Option<&'a T> {
+        self.position(id)
+            .and_then(|i| objects.get(i))
+            .filter(|object| object.id() == id)
+    }
+
+    /// Get the object with `uuid` from the indexed `objects`.
+    pub fn get_by_uuid<'a, T: LrObject>(&self, objects: &'a [T], uuid: &str) -> Option<&'a T> {
+        self.position_by_uuid(uuid)
+            .and_then(|i| objects.get(i))
+            .filter(|object| object.uuid() == uuid)
+    }
+}
+
+/// Any of the `LrObject` from the `Catalog`. Returned by
+/// `Catalog::by_uuid()`.
+#[derive(Clone, Copy)]
+pub enum CatalogObject<'a> {
+    Image(&'a Image),
+    LibraryFile(&'a LibraryFile),
+    Folder(&'a Folder),
+    RootFolder(&'a RootFolder),
+    Keyword(&'a Keyword),
+}
+
+impl LrObject for CatalogObject<'_> {
+    fn id(&self) -> LrId {
+        match self {
+            Self::Image(o) => o.id(),
+            Self::LibraryFile(o) => o.id(),
+            Self::Folder(o) => o.id(),
+            Self::RootFolder(o) => o.id(),
+            Self::Keyword(o) => o.id(),
+        }
+    }
+
+    fn uuid(&self) -> &str {
+        match self {
+            Self::Image(o) => o.uuid(),
+            Self::LibraryFile(o) => o.uuid(),
+            Self::Folder(o) => o.uuid(),
+            Self::RootFolder(o) => o.uuid(),
+            Self::Keyword(o) => o.uuid(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_object_index() {
+    let folders = vec![Folder::new(42, "foo"), Folder::new(43, "bar")];
+    let index = ObjectIndex::new(&folders);
+
+    assert_eq!(index.get(&folders, 43).map(|f| f.uuid()), Some("bar"));
+    assert_eq!(index.get_by_uuid(&folders, "foo").map(|f| f.id()), Some(42));
+    assert!(index.get(&folders, 1).is_none());
+    // Stale index
+    assert!(index.get(&folders[1..], 42).is_none());
+}
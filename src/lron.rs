@@ -36,22 +36,55 @@
 //!
 //! let lron_text = "name = {}"; // load the text in the string
 //!
-//! if let Ok(object) = lron::Object::from_string(lron_text) {
+//! if let Ok(value) = lron::Value::from_string(lron_text) {
 //!     // do your stuff with it
 //! }
 //! ```
+//!
+//! Everything is a `Value`. A dictionary is really a Lua table: it
+//! contains `Value::Pair` for the keyed entries, and any other value
+//! for the positional ones.
+//!
+//! Previous versions had a separate `Object` type for the content of
+//! dictionaries, that couldn't hold floats or booleans. `Object` is
+//! now a deprecated alias to `Value`, and `Object::from_string()`
+//! still works. This is a breaking change: `Object::Pair` holds a
+//! `Box<Pair>`, so patterns like `Object::Pair(Pair { .. })` must
+//! match the box instead, and `Value::Dict` holds `Value`s.
+//!
+//! Some string values are themselves lron, like the smart collection
+//! criteria. Use `Value::from_string_nested()` to have them decoded
+//! as `Value::Nested`.
+//!
+//! `borrowed::Value` is the zero-copy variant, for when parsing a
+//! lot of lron.
+//!
+//! With the `json` feature, `Value::to_json()` convert the lron into
+//! a `serde_json::Value`.
+
+pub mod borrowed;
 
 /// Lron Value
 #[derive(Clone, Debug, PartialEq)]
 pub enum Value {
-    Dict(Vec<Object>),
+    /// A dictionary, or an array.
+    Dict(Vec<Value>),
+    /// A key/value pair. Found at the top level, or in a `Dict`.
+    Pair(Box<Pair>),
     Str(String),
     ZStr(String),
     Int(i32),
     Float(f64),
     Bool(bool),
+    /// A string value that is itself lron, decoded by
+    /// `Value::decode_nested()`.
+    Nested(Box<Value>),
 }
 
+/// Lron Object. This is now `Value`.
+#[deprecated(since = "0.6.0", note = "Use lron::Value")]
+pub type Object = Value;
+
 impl Value {
     /// Try to convert the value into a number of type T.  This is
     /// because number are untyped in Lron, and the parser will manage
@@ -67,6 +100,24 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Decode the string values that are themselves lron into
+    /// `Value::Nested`, recursively. Strings that don't parse are left
+    /// untouched. This is opt-in as it requires trying to parse every
+    /// string value.
+    pub fn decode_nested(&mut self) {
+        match self {
+            Self::Dict(ref mut d) => d.iter_mut().for_each(Value::decode_nested),
+            Self::Pair(ref mut p) => p.value.decode_nested(),
+            Self::Str(ref s) => {
+                if let Ok(nested) = Value::from_string_nested(s) {
+                    *self = Self::Nested(Box::new(nested));
+                }
+            }
+            Self::Nested(ref mut v) => v.decode_nested(),
+            _ => {}
+        }
+    }
 }
 
 /// A key/value pair.
@@ -76,54 +127,232 @@ pub struct Pair {
     pub value: Value,
 }
 
-/// Lron Object
-#[derive(Clone, Debug, PartialEq)]
-pub enum Object {
-    Dict(Vec<Object>),
-    Pair(Pair),
-    Str(String),
-    ZStr(String),
-    Int(i32),
+impl From<Pair> for Value {
+    fn from(pair: Pair) -> Value {
+        Value::Pair(Box::new(pair))
+    }
 }
 
 /// Alias result type for parsing a Lron object.
 type Result<T> = std::result::Result<T, peg::error::ParseError<peg::str::LineCol>>;
 
-impl Object {
-    /// Create an object from a string
-    pub fn from_string(s: &str) -> Result<Object> {
-        lron::root(s)
+/// A diagnostic from the lenient parser, for a value that was
+/// skipped.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    /// Line of the error, starting at 1.
+    pub line: usize,
+    /// Column of the error, starting at 1.
+    pub column: usize,
+    /// The tokens the parser expected.
+    pub expected: Vec<String>,
+    /// The text that was skipped.
+    pub snippet: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: skipped {:?}",
+            self.line, self.column, self.snippet
+        )?;
+        if !self.expected.is_empty() {
+            write!(f, ", expected {}", self.expected.join(", "))?;
+        }
+        Ok(())
     }
 }
 
+/// A value skipped by the parser in lenient mode.
+struct Recovery {
+    /// Byte offset of the skipped text.
+    start: usize,
+    /// Byte offset of the end of the skipped text.
+    end: usize,
+}
+
+/// The parser context.
+#[derive(Default)]
+struct ParseContext {
+    /// Whether to recover from errors.
+    lenient: bool,
+    /// The values skipped in lenient mode.
+    recovered: std::cell::RefCell<Vec<Recovery>>,
+}
+
+/// Parse `s`, recovering from errors if `lenient`.
+fn parse(s: &str, lenient: bool) -> Result<(borrowed::Value<'_>, Vec<Diagnostic>)> {
+    let ctx = ParseContext {
+        lenient,
+        ..ParseContext::default()
+    };
+    let value = lron::root(s, &ctx)?;
+
+    let mut recovered = ctx.recovered.into_inner();
+    recovered.sort_by_key(|r| (r.start, std::cmp::Reverse(r.end)));
+    // Backtracking may have recorded a recovery inside a value
+    // that got skipped as a whole.
+    let mut end = 0;
+    recovered.retain(|r| {
+        let keep = r.start >= end;
+        if keep {
+            end = r.end;
+        }
+        keep
+    });
+
+    let diagnostics = recovered
+        .iter()
+        .map(|r| {
+            let snippet = &s[r.start..r.end];
+            // Parse the value strictly to know what went wrong.
+            let (offset, expected) = match lron::fragment(snippet, &ParseContext::default()) {
+                Err(e) => (
+                    r.start + e.location.offset,
+                    e.expected.tokens().map(str::to_owned).collect(),
+                ),
+                Ok(_) => (r.start, vec![]),
+            };
+            let location = peg::Parse::position_repr(s, offset);
+            Diagnostic {
+                line: location.line,
+                column: location.column,
+                expected,
+                snippet: snippet.trim().to_owned(),
+            }
+        })
+        .collect();
+
+    Ok((value, diagnostics))
+}
+
+impl Value {
+    /// Create a value from a string
+    pub fn from_string(s: &str) -> Result<Value> {
+        borrowed::Value::from_string(s).map(borrowed::Value::into_owned)
+    }
+
+    /// Create a value from a string, skipping the values that fail
+    /// to parse. Parsing resume at the next `,` or `}`. Return the
+    /// partial value and a diagnostic for each value skipped.
+    ///
+    /// This still fail if the enclosing structure is broken.
+    pub fn from_string_lenient(s: &str) -> Result<(Value, Vec<Diagnostic>)> {
+        borrowed::Value::from_string_lenient(s)
+            .map(|(value, diagnostics)| (value.into_owned(), diagnostics))
+    }
+
+    /// Create a value from a string, and decode the nested lron
+    /// strings. See `decode_nested()`.
+    pub fn from_string_nested(s: &str) -> Result<Value> {
+        let mut value = Self::from_string(s)?;
+        value.decode_nested();
+        Ok(value)
+    }
+}
+
+#[cfg(feature = "json")]
+impl Value {
+    /// Convert the value to JSON. A `Pair` becomes a JSON object
+    /// with a single key. Non finite floats become `null`.
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::Value as Json;
+
+        match self {
+            Self::Dict(d) => dict_to_json(d),
+            Self::Pair(p) => {
+                let mut map = serde_json::Map::new();
+                map.insert(p.key.clone(), p.value.to_json());
+                Json::Object(map)
+            }
+            Self::Str(s) | Self::ZStr(s) => Json::String(s.clone()),
+            Self::Int(i) => Json::from(*i),
+            Self::Float(f) => serde_json::Number::from_f64(*f).map_or(Json::Null, Json::Number),
+            Self::Bool(b) => Json::Bool(*b),
+            Self::Nested(v) => v.to_json(),
+        }
+    }
+}
+
+/// Convert a lron dictionary (a Lua table really) to JSON.  If it
+/// only contain pairs, it is an object, otherwise it is an array.
+/// Mixed tables are converted to an object where the positional
+/// values are keyed with their index, starting at 1 like in Lua.
+#[cfg(feature = "json")]
+fn dict_to_json(d: &[Value]) -> serde_json::Value {
+    use serde_json::Value as Json;
+
+    let pairs = d.iter().filter(|v| matches!(v, Value::Pair(_))).count();
+    if pairs == 0 && !d.is_empty() {
+        return Json::Array(d.iter().map(Value::to_json).collect());
+    }
+
+    let mut map = serde_json::Map::new();
+    let mut index = 1;
+    for v in d {
+        if let Value::Pair(p) = v {
+            map.insert(p.key.clone(), p.value.to_json());
+        } else {
+            map.insert(index.to_string(), v.to_json());
+            index += 1;
+        }
+    }
+    Json::Object(map)
+}
+
 // lron stand for Lightroom Object Notation
 // Some sort of JSON specific to Lightroom
 //
 // lron data syntax is defined in this PEG grammar.
-peg::parser! {grammar lron() for str {
+peg::parser! {grammar lron(ctx: &ParseContext) for str {
 
+use std::borrow::Cow;
 use std::str::FromStr;
 
-pub rule root() -> Object
+use super::borrowed::{Pair, Value};
+
+pub rule root() -> Value<'input>
         = key:identifier() _() "=" _() value:array() _()
-    { Object::Pair(Pair{key, value: Value::Dict(value)}) }
+    { Pair { key, value: Value::Dict(value) }.into() }
 
-rule array() -> Vec<Object>
-        = "{" _() v:(object() ** (_() "," _())) _()(",")? _() "}" { v }
+// A single entry, used to diagnose a recovery.
+pub rule fragment() -> Value<'input>
+        = _() e:entry() _() { e }
 
-rule object() -> Object
-        = a:array() { Object::Dict(a) } /
-        p:pair() { Object::Pair(p) } /
-        s:string_literal() { Object::Str(s) } /
-        z:zstr() { Object::ZStr(z) } /
-        n:int() { Object::Int(n) }
+rule array() -> Vec<Value<'input>>
+        = "{" _() v:(element() ** (_() "," _())) _()(",")? _() "}"
+    { v.into_iter().flatten().collect() }
 
-rule pair() -> Pair
+rule element() -> Option<Value<'input>>
+        = e:entry() &(_() ("," / "}")) { Some(e) } /
+        recover() { None }
+
+// In lenient mode, skip to the next `,` or `}`.
+rule recover()
+        = quiet!{lenient() start:position!() junk() end:position!()
+    { ctx.recovered.borrow_mut().push(Recovery { start, end }) }}
+
+rule lenient()
+        = {? if ctx.lenient { Ok(()) } else { Err("lenient") } }
+
+rule junk()
+        = (string_literal() / "{" junk_inner() "}" / !['{' | '}' | ','] [_])+
+
+rule junk_inner()
+        = (string_literal() / "{" junk_inner() "}" / !['{' | '}'] [_])*
+
+// An entry in a dictionary: either keyed or positional.
+rule entry() -> Value<'input>
+        = p:pair() { p.into() } /
+        value()
+
+rule pair() -> Pair<'input>
         = key:identifier() _() "=" _() value:value() { Pair { key, value } } /
         "[" key:string_literal() "]" _() "=" _() value:value()
     { Pair { key, value } }
 
-rule value() -> Value
+rule value() -> Value<'input>
         = i:int() { Value::Int(i) } /
         b:bool() { Value::Bool(b) } /
         f:float() { Value::Float(f) } /
@@ -132,7 +361,7 @@ rule value() -> Value
         z:zstr() { Value::ZStr(z) }
 
 rule int() -> i32
-        = n:$("-"? ['0'..='9']+) !"." { i32::from_str(n).unwrap() } / expected!("integer")
+        = n:$("-"? ['0'..='9']+) !"." {? i32::from_str(n).or(Err("integer")) } / expected!("integer")
 
 rule bool() -> bool
         = "true" { true } / "false" { false }
@@ -140,18 +369,19 @@ rule bool() -> bool
 rule float() -> f64
         = f:$("-"? ['0'..='9']+ "." ['0'..='9']+) { f64::from_str(f).unwrap() } / expected!("floating point")
 
-rule identifier() -> String
-        = s:$(['a'..='z' | 'A'..='Z' | '0'..='9' | '_']+) { s.to_owned() } / expected!("identifier")
+rule identifier() -> Cow<'input, str>
+        = s:$(['a'..='z' | 'A'..='Z' | '0'..='9' | '_']+) { Cow::Borrowed(s) } / expected!("identifier")
 
 // String escape, either literal EOL or quotes.
 rule escape() -> &'static str
         = "\\\"" { "\"" } / "\\\n" { "\n" }
 
-// String literal can be escaped.
-rule string_literal() -> String
-        = "\"" s:((escape() / $(!['"'][_]))*) "\"" { s.join("") }
+// String literal can be escaped. Only allocate if it is.
+rule string_literal() -> Cow<'input, str>
+        = "\"" s:$((!['"' | '\\'][_])*) "\"" { Cow::Borrowed(s) } /
+        "\"" s:((escape() / $(!['"'][_]))*) "\"" { Cow::Owned(s.join("")) }
 
-rule zstr() -> String
+rule zstr() -> Cow<'input, str>
         = "ZSTR" _() s:string_literal() { s }
 
 rule _() = quiet!{[' ' | '\r' | '\n' | '\t']*}
@@ -161,30 +391,30 @@ rule _() = quiet!{[' ' | '\r' | '\n' | '\t']*}
 #[test]
 fn test_parser() {
     const DATA: &str = include_str!("../data/test_lron");
-    let r = Object::from_string(DATA);
+    let r = Value::from_string(DATA);
 
     assert!(r.is_ok());
     let o = r.unwrap();
 
-    assert!(matches!(o, Object::Pair(_)));
-    if let Object::Pair(ref p) = o {
+    assert!(matches!(o, Value::Pair(_)));
+    if let Value::Pair(ref p) = o {
         assert_eq!(p.key, "s");
         assert!(matches!(p.value, Value::Dict(_)));
 
         if let Value::Dict(ref d) = p.value {
             assert_eq!(d.len(), 2);
-            assert!(matches!(d[0], Object::Dict(_)));
-            if let Object::Dict(ref d) = d[0] {
+            assert!(matches!(d[0], Value::Dict(_)));
+            if let Value::Dict(ref d) = d[0] {
                 assert_eq!(d.len(), 5);
-                assert!(matches!(d[0], Object::Pair(_)));
-                assert!(matches!(d[1], Object::Pair(_)));
-                assert!(matches!(d[2], Object::Pair(_)));
-                assert!(matches!(d[3], Object::Pair(_)));
-                assert!(matches!(d[4], Object::Pair(_)));
-                if let Object::Pair(ref p) = d[4] {
+                assert!(matches!(d[0], Value::Pair(_)));
+                assert!(matches!(d[1], Value::Pair(_)));
+                assert!(matches!(d[2], Value::Pair(_)));
+                assert!(matches!(d[3], Value::Pair(_)));
+                assert!(matches!(d[4], Value::Pair(_)));
+                if let Value::Pair(ref p) = d[4] {
                     assert_eq!(p.key, "someOther");
                     if let Value::Str(value) = &p.value {
-                        let r2 = Object::from_string(value);
+                        let r2 = Value::from_string(value);
                         assert!(r2.is_ok());
                     }
                     assert_eq!(
@@ -198,8 +428,8 @@ fn test_parser() {
                     );
                 }
             }
-            assert!(matches!(d[1], Object::Pair(_)));
-            if let Object::Pair(ref p) = d[1] {
+            assert!(matches!(d[1], Value::Pair(_)));
+            if let Value::Pair(ref p) = d[1] {
                 assert_eq!(p.key, "combine");
                 assert_eq!(p.value, Value::Str("intersect".to_owned()));
             }
@@ -208,3 +438,209 @@ fn test_parser() {
         unreachable!();
     }
 }
+
+#[test]
+fn test_decode_nested() {
+    const DATA: &str = include_str!("../data/test_lron");
+    let o = Value::from_string_nested(DATA).expect("Parsing failed");
+
+    let Value::Pair(ref p) = o else {
+        unreachable!();
+    };
+    let Value::Dict(ref d) = p.value else {
+        unreachable!();
+    };
+    let Value::Dict(ref d) = d[0] else {
+        unreachable!();
+    };
+    // Non lron strings are left alone.
+    assert_eq!(
+        d[0],
+        Pair {
+            key: "criteria".to_owned(),
+            value: Value::Str("rating".to_owned())
+        }
+        .into()
+    );
+    let Value::Pair(ref p) = d[4] else {
+        unreachable!();
+    };
+    assert_eq!(p.key, "someOther");
+    assert_eq!(
+        p.value,
+        Value::Nested(Box::new(
+            Pair {
+                key: "anObject".to_owned(),
+                value: Value::Dict(vec![Pair {
+                    key: "key".to_owned(),
+                    value: Value::Str("lr".to_owned())
+                }
+                .into()])
+            }
+            .into()
+        ))
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_to_json() {
+    const DATA: &str = include_str!("../data/test_lron");
+    let o = Value::from_string_nested(DATA).expect("Parsing failed");
+
+    assert_eq!(
+        o.to_json(),
+        serde_json::json!({
+            "s": {
+                "1": {
+                    "criteria": "rating",
+                    "operation": ">",
+                    "value": 0,
+                    "value2": 0,
+                    "someOther": { "anObject": { "key": "lr" } },
+                },
+                "combine": "intersect",
+            }
+        })
+    );
+
+    let o = Value::from_string("a = { 1, 2, \"three\" }").expect("Parsing failed");
+    assert_eq!(o.to_json(), serde_json::json!({ "a": [1, 2, "three"] }));
+}
+
+#[test]
+fn test_lenient_parser() {
+    const DATA: &str = "s = {\n\
+                        a = 1,\n\
+                        b = ,\n\
+                        c = { x = 1.5.2, y = \"why\" },\n\
+                        d = \"dee\",\n\
+                        }";
+
+    assert!(Value::from_string(DATA).is_err());
+
+    let (o, diagnostics) = Value::from_string_lenient(DATA).expect("Parsing failed");
+    assert_eq!(
+        o,
+        Pair {
+            key: "s".to_owned(),
+            value: Value::Dict(vec![
+                Pair {
+                    key: "a".to_owned(),
+                    value: Value::Int(1)
+                }
+                .into(),
+                Pair {
+                    key: "c".to_owned(),
+                    value: Value::Dict(vec![Pair {
+                        key: "y".to_owned(),
+                        value: Value::Str("why".to_owned())
+                    }
+                    .into()])
+                }
+                .into(),
+                Pair {
+                    key: "d".to_owned(),
+                    value: Value::Str("dee".to_owned())
+                }
+                .into(),
+            ])
+        }
+        .into()
+    );
+
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].line, 3);
+    assert_eq!(diagnostics[0].column, 5);
+    assert_eq!(diagnostics[0].snippet, "b =");
+    assert!(diagnostics[0].expected.contains(&"\"{\"".to_owned()));
+    assert_eq!(diagnostics[1].line, 4);
+    assert_eq!(diagnostics[1].column, 14);
+    assert_eq!(diagnostics[1].snippet, "x = 1.5.2");
+
+    // Broken structure can't be recovered.
+    assert!(Value::from_string_lenient("s = { a = 1").is_err());
+
+    // Out of range integer.
+    const OVERFLOW: &str = "s = { a = 99999999999, b = 2 }";
+    assert!(Value::from_string(OVERFLOW).is_err());
+    let (o, diagnostics) = Value::from_string_lenient(OVERFLOW).expect("Parsing failed");
+    assert_eq!(
+        o,
+        Pair {
+            key: "s".to_owned(),
+            value: Value::Dict(vec![Pair {
+                key: "b".to_owned(),
+                value: Value::Int(2)
+            }
+            .into()])
+        }
+        .into()
+    );
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].snippet, "a = 99999999999");
+    assert!(diagnostics[0].expected.contains(&"integer".to_owned()));
+}
+
+#[test]
+fn test_mixed_arrays() {
+    const DATA: &str = "s = {\n\
+                        ToneCurvePV2012 = { 0, 0.5, 255, 254.5 },\n\
+                        flags = { true, false },\n\
+                        mixed = { 1, 2.5, \"three\", false, x = 1 },\n\
+                        }";
+
+    let v = Value::from_string(DATA).expect("Parsing failed");
+    let Value::Pair(ref p) = v else {
+        unreachable!();
+    };
+    let Value::Dict(ref d) = p.value else {
+        unreachable!();
+    };
+    assert_eq!(
+        d[0],
+        Pair {
+            key: "ToneCurvePV2012".to_owned(),
+            value: Value::Dict(vec![
+                Value::Int(0),
+                Value::Float(0.5),
+                Value::Int(255),
+                Value::Float(254.5)
+            ])
+        }
+        .into()
+    );
+    assert_eq!(
+        d[1],
+        Pair {
+            key: "flags".to_owned(),
+            value: Value::Dict(vec![Value::Bool(true), Value::Bool(false)])
+        }
+        .into()
+    );
+    assert_eq!(
+        d[2],
+        Pair {
+            key: "mixed".to_owned(),
+            value: Value::Dict(vec![
+                Value::Int(1),
+                Value::Float(2.5),
+                Value::Str("three".to_owned()),
+                Value::Bool(false),
+                Pair {
+                    key: "x".to_owned(),
+                    value: Value::Int(1)
+                }
+                .into()
+            ])
+        }
+        .into()
+    );
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_object_alias() {
+    let o: Object = Object::from_string("a = { 1.5 }").expect("Parsing failed");
+    assert!(matches!(o, Object::Pair(_)));
+}
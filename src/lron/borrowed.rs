@@ -0,0 +1,137 @@
+/*
+ This Source Code Form is subject to the terms of the Mozilla Public
+ License, v. 2.0. If a copy of the MPL was not distributed with this
+ file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+//! Borrowed lron values. Keys and strings borrow from the parsed
+//! text instead of being allocated, unless they contain escapes.
+//! Use this to scan large amounts of lron, and `into_owned()` to keep
+//! what you need.
+//!
+//! ```
+//! use lrcat::lron::borrowed;
+//!
+//! let lron_text = "name = { x = 1.3 }";
+//!
+//! if let Ok(value) = borrowed::Value::from_string(lron_text) {
+//!     let owned: lrcat::lron::Value = value.into_owned();
+//! }
+//! ```
+
+use std::borrow::Cow;
+
+use super::{Diagnostic, Result};
+
+/// Borrowed Lron Value. See `lron::Value`.
+///
+/// There is no `Nested` variant: nested lron strings can only be
+/// decoded on the owned `lron::Value`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'a> {
+    Dict(Vec<Value<'a>>),
+    Pair(Box<Pair<'a>>),
+    Str(Cow<'a, str>),
+    ZStr(Cow<'a, str>),
+    Int(i32),
+    Float(f64),
+    Bool(bool),
+}
+
+/// Borrowed Lron Object. This is now `Value`.
+#[deprecated(since = "0.6.0", note = "Use lron::borrowed::Value")]
+pub type Object<'a> = Value<'a>;
+
+impl<'a> Value<'a> {
+    /// Create a value borrowing from a string.
+    pub fn from_string(s: &'a str) -> Result<Value<'a>> {
+        super::parse(s, false).map(|(value, _)| value)
+    }
+
+    /// Create a value borrowing from a string, skipping the values
+    /// that fail to parse. See `lron::Value::from_string_lenient()`.
+    pub fn from_string_lenient(s: &'a str) -> Result<(Value<'a>, Vec<Diagnostic>)> {
+        super::parse(s, true)
+    }
+
+    /// Try to convert the value into a number of type T.
+    /// See `lron::Value::to_number()`.
+    pub fn to_number<T>(&self) -> Option<T>
+    where
+        T: std::convert::From<i32> + std::convert::From<f64>,
+    {
+        match *self {
+            Self::Int(i) => Some(i.into()),
+            Self::Float(f) => Some(f.into()),
+            _ => None,
+        }
+    }
+
+    /// Convert into an owned `lron::Value`.
+    pub fn into_owned(self) -> super::Value {
+        match self {
+            Self::Dict(d) => super::Value::Dict(d.into_iter().map(Value::into_owned).collect()),
+            Self::Pair(p) => super::Value::Pair(Box::new(p.into_owned())),
+            Self::Str(s) => super::Value::Str(s.into_owned()),
+            Self::ZStr(s) => super::Value::ZStr(s.into_owned()),
+            Self::Int(i) => super::Value::Int(i),
+            Self::Float(f) => super::Value::Float(f),
+            Self::Bool(b) => super::Value::Bool(b),
+        }
+    }
+}
+
+/// A borrowed key/value pair.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pair<'a> {
+    pub key: Cow<'a, str>,
+    pub value: Value<'a>,
+}
+
+impl Pair<'_> {
+    /// Convert into an owned `lron::Pair`.
+    pub fn into_owned(self) -> super::Pair {
+        super::Pair {
+            key: self.key.into_owned(),
+            value: self.value.into_owned(),
+        }
+    }
+}
+
+impl<'a> From<Pair<'a>> for Value<'a> {
+    fn from(pair: Pair<'a>) -> Value<'a> {
+        Value::Pair(Box::new(pair))
+    }
+}
+
+#[test]
+fn test_borrowed_parser() {
+    const DATA: &str = include_str!("../../data/test_lron");
+    let v = Value::from_string(DATA).expect("Parsing failed");
+
+    let Value::Pair(ref p) = v else {
+        panic!("Expected a pair, got {:?}", v);
+    };
+    assert!(matches!(p.key, Cow::Borrowed("s")));
+    let Value::Dict(ref d) = p.value else {
+        panic!("Expected a dict, got {:?}", p.value);
+    };
+    let Value::Dict(ref d) = d[0] else {
+        panic!("Expected a dict, got {:?}", d[0]);
+    };
+    // No escape: borrowed.
+    let Value::Pair(ref p) = d[0] else {
+        panic!("Expected a pair, got {:?}", d[0]);
+    };
+    assert!(matches!(p.value, Value::Str(Cow::Borrowed("rating"))));
+    // Escaped: owned.
+    let Value::Pair(ref p) = d[4] else {
+        panic!("Expected a pair, got {:?}", d[4]);
+    };
+    assert!(matches!(p.value, Value::Str(Cow::Owned(_))));
+
+    assert_eq!(
+        v.into_owned(),
+        super::Value::from_string(DATA).expect("Parsing failed")
+    );
+}
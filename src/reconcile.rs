@@ -0,0 +1,371 @@
+/*
+ This Source Code Form is subject to the terms of the Mozilla Public
+ License, v. 2.0. If a copy of the MPL was not distributed with this
+ file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::catalog::Catalog;
+use crate::folders::{Folder, LocatedPath, PathStrategy};
+use crate::libraryfiles::{LibraryFile, Sidecar, SidecarKind};
+use crate::lrobject::{LrId, LrObject};
+
+/// The state of a file on disk compared to the catalog.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum FileStatus {
+    /// The file is on disk, unchanged.
+    Present,
+    /// The file isn't on disk.
+    Missing,
+    /// The file is on disk but not in the catalog.
+    New,
+    /// The file was modified after Lightroom last saw it.
+    Modified,
+    /// The file is on disk, but its sidecars don't match the catalog.
+    SidecarMismatch,
+}
+
+/// A file compared by the `Reconciler`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReconcileEntry {
+    /// The status of the file.
+    pub status: FileStatus,
+    /// The path of the file. For a missing file, where it was
+    /// expected.
+    pub path: PathBuf,
+    /// The `Folder` id.
+    pub folder: LrId,
+    /// The `LibraryFile` id, `None` for a new file.
+    pub file: Option<LrId>,
+    /// The sidecars in the catalog not on disk.
+    pub missing_sidecars: Vec<PathBuf>,
+    /// The sidecars on disk not in the catalog.
+    pub unexpected_sidecars: Vec<PathBuf>,
+}
+
+/// The result of the reconciliation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReconcileReport {
+    /// The files, by folder.
+    pub entries: Vec<ReconcileEntry>,
+    /// The folders that couldn't be found on disk. Their files are
+    /// all `FileStatus::Missing`.
+    pub missing_folders: Vec<LrId>,
+}
+
+impl ReconcileReport {
+    /// The entries with `status`.
+    pub fn with_status(&self, status: FileStatus) -> impl Iterator<Item = &ReconcileEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.status == status)
+    }
+
+    /// The number of entries for each status.
+    pub fn counts(&self) -> BTreeMap<FileStatus, usize> {
+        let mut counts = BTreeMap::new();
+        for entry in &self.entries {
+            *counts.entry(entry.status).or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// Compare the folders of the catalog with the files on disk. The
+/// folders and library files must have been loaded. Folders are
+/// located like with `Catalog::locate_folder()`. Hidden files are
+/// ignored.
+pub struct Reconciler<'a> {
+    catalog: &'a Catalog,
+    /// Tolerance on the modification time.
+    tolerance: Duration,
+}
+
+impl<'a> Reconciler<'a> {
+    pub fn new(catalog: &'a Catalog) -> Reconciler<'a> {
+        Reconciler {
+            catalog,
+            // FAT has a 2 seconds resolution.
+            tolerance: Duration::seconds(2),
+        }
+    }
+
+    /// Reconcile all the folders.
+    pub fn reconcile(&self) -> ReconcileReport {
+        let mut files = BTreeMap::<LrId, Vec<&LibraryFile>>::new();
+        for file in self.catalog.libfiles() {
+            files.entry(file.folder).or_default().push(file);
+        }
+
+        let mut report = ReconcileReport::default();
+        for folder in self.catalog.folders().folders() {
+            let files = files.remove(&folder.id()).unwrap_or_default();
+            if !self.reconcile_files(folder, &files, &mut report.entries) {
+                report.missing_folders.push(folder.id());
+            }
+        }
+        report
+    }
+
+    /// Reconcile `folder`.
+    pub fn reconcile_folder(&self, folder: &Folder) -> ReconcileReport {
+        let files = self
+            .catalog
+            .libfiles()
+            .iter()
+            .filter(|file| file.folder == folder.id())
+            .collect::<Vec<_>>();
+        let mut report = ReconcileReport::default();
+        if !self.reconcile_files(folder, &files, &mut report.entries) {
+            report.missing_folders.push(folder.id());
+        }
+        report
+    }
+
+    /// Reconcile `files` of `folder`, adding the entries. Return
+    /// `false` if the folder wasn't found.
+    fn reconcile_files(
+        &self,
+        folder: &Folder,
+        files: &[&LibraryFile],
+        entries: &mut Vec<ReconcileEntry>,
+    ) -> bool {
+        let located = match self.catalog.locate_folder(folder) {
+            Some(located) => located,
+            // No root folder: nowhere to look.
+            None => LocatedPath {
+                path: PathBuf::new(),
+                strategy: PathStrategy::NotFound,
+            },
+        };
+        let on_disk = if located.strategy == PathStrategy::NotFound {
+            None
+        } else {
+            std::fs::read_dir(&located.path).ok().map(|dir| {
+                let mut names = dir
+                    .filter_map(Result::ok)
+                    .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+                    .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                    .filter(|name| !name.starts_with('.'))
+                    .collect::<Vec<String>>();
+                names.sort();
+                names
+            })
+        };
+        let entry = |status, name: &str, file: Option<LrId>| ReconcileEntry {
+            status,
+            path: located.path.join(name),
+            folder: folder.id(),
+            file,
+            missing_sidecars: vec![],
+            unexpected_sidecars: vec![],
+        };
+
+        let on_disk =
+            match on_disk {
+                Some(on_disk) => on_disk,
+                None => {
+                    entries.extend(files.iter().map(|file| {
+                        entry(FileStatus::Missing, &file.file_name(), Some(file.id()))
+                    }));
+                    return false;
+                }
+            };
+        let disk_names = on_disk.iter().map(String::as_str).collect::<HashSet<_>>();
+        let catalog_names = files
+            .iter()
+            .map(|file| file.file_name())
+            .collect::<HashSet<_>>();
+        let mut matched = HashSet::new();
+
+        for file in files {
+            let name = file.file_name();
+            if !disk_names.contains(name.as_str()) {
+                entries.push(entry(FileStatus::Missing, &name, Some(file.id())));
+                continue;
+            }
+            matched.insert(name.clone());
+            let mut e = entry(FileStatus::Present, &name, Some(file.id()));
+
+            let expected = file.sidecar_file_names();
+            for sidecar in &expected {
+                if disk_names.contains(sidecar.as_str()) {
+                    matched.insert(sidecar.clone());
+                } else {
+                    e.missing_sidecars.push(located.path.join(sidecar));
+                }
+            }
+            // Same basename, a sidecar type, not expected.
+            let prefix = format!("{}.", file.basename);
+            for other in &on_disk {
+                if let Some(ext) = other.strip_prefix(&prefix) {
+                    if Sidecar::new(ext).kind != SidecarKind::Other
+                        && !expected.contains(other)
+                        && !catalog_names.contains(other)
+                    {
+                        matched.insert(other.clone());
+                        e.unexpected_sidecars.push(located.path.join(other));
+                    }
+                }
+            }
+
+            if self.is_modified(file, &e.path) {
+                e.status = FileStatus::Modified;
+            } else if !e.missing_sidecars.is_empty() || !e.unexpected_sidecars.is_empty() {
+                e.status = FileStatus::SidecarMismatch;
+            }
+            entries.push(e);
+        }
+
+        entries.extend(
+            on_disk
+                .iter()
+                .filter(|name| !matched.contains(*name))
+                .map(|name| entry(FileStatus::New, name, None)),
+        );
+        true
+    }
+
+    /// Whether the file at `path` was modified after `file` last
+    /// modification time in the catalog.
+    fn is_modified(&self, file: &LibraryFile, path: &std::path::Path) -> bool {
+        let modified = std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .map(DateTime::<Utc>::from);
+        match (file.external_mod_time, modified) {
+            (Some(known), Ok(modified)) => modified > known + self.tolerance,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{FileStatus, Reconciler};
+    use crate::catalog::tests::open_test_catalog;
+    use crate::remap::{PathRemap, RemapRule};
+
+    #[test]
+    fn test_reconcile() {
+        let (dir, mut catalog) = open_test_catalog("");
+        catalog.load_folders().unwrap();
+        catalog.load_library_files().unwrap();
+        let photos = dir.path().join("Pictures");
+        let mut remap = PathRemap::new();
+        remap.add_rule(RemapRule::new(
+            "/home/user/Pictures",
+            &photos.to_string_lossy(),
+        ));
+        catalog.set_path_remap(remap);
+
+        // Before the externalModTime of the fixture.
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+        let write = |name: &str, mtime: Option<SystemTime>| {
+            let path = photos.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, name).unwrap();
+            if let Some(mtime) = mtime {
+                std::fs::File::options()
+                    .write(true)
+                    .open(&path)
+                    .unwrap()
+                    .set_modified(mtime)
+                    .unwrap();
+            }
+        };
+        write("2017/10/IMG_0001.CR2", Some(old));
+        write("2017/10/IMG_0001.JPG", None);
+        write("2017/10/IMG_0001.xmp", None);
+        write("2017/10/IMG_0002.JPG", None);
+        write("2017/10/IMG_0003.CR2", None);
+        write("2017/10/.DS_Store", None);
+        write("2017/DSC_0003.NEF", Some(old));
+        write("2017/DSC_0005.DNG", Some(old));
+        write("2017/DSC_0005.xmp", Some(old));
+
+        let report = Reconciler::new(&catalog).reconcile();
+        let status = |name: &str| {
+            report
+                .entries
+                .iter()
+                .find(|entry| entry.path == photos.join(name))
+                .map(|entry| entry.status)
+        };
+        assert_eq!(status("2017/10/IMG_0001.CR2"), Some(FileStatus::Present));
+        assert_eq!(status("2017/10/IMG_0001.xmp"), None);
+        assert_eq!(status("2017/10/IMG_0002.JPG"), Some(FileStatus::Modified));
+        assert_eq!(status("2017/10/IMG_0003.CR2"), Some(FileStatus::New));
+        assert_eq!(status("2017/10/.DS_Store"), None);
+        assert_eq!(
+            status("2017/DSC_0003.NEF"),
+            Some(FileStatus::SidecarMismatch)
+        );
+        assert_eq!(status("2017/DSC_0004.DNG"), Some(FileStatus::Missing));
+        assert_eq!(status("2017/DSC_0005.DNG"), Some(FileStatus::New));
+        assert_eq!(status("2017/DSC_0005.xmp"), Some(FileStatus::New));
+        assert_eq!(report.entries.len(), 7);
+        assert!(report.missing_folders.is_empty());
+
+        let entry = report
+            .with_status(FileStatus::SidecarMismatch)
+            .next()
+            .unwrap();
+        assert_eq!(entry.file, Some(22));
+        assert_eq!(
+            entry.missing_sidecars,
+            vec![photos.join("2017/DSC_0003.xmp")]
+        );
+        assert_eq!(report.counts()[&FileStatus::New], 3);
+
+        std::fs::remove_dir_all(photos.join("2017/10")).unwrap();
+        let report = Reconciler::new(&catalog).reconcile_folder(catalog.folder(13).unwrap());
+        assert_eq!(report.missing_folders, vec![13]);
+        assert_eq!(report.with_status(FileStatus::Missing).count(), 2);
+    }
+
+    #[test]
+    fn test_reconcile_relative_root() {
+        // Like a catalog on a portable drive.
+        const SQL: &str = "UPDATE AgLibraryRootFolder SET relativePathFromCatalog = 'Photos/';";
+        let (dir, mut catalog) = open_test_catalog(SQL);
+        catalog.load_folders().unwrap();
+        catalog.load_library_files().unwrap();
+        catalog.load_images().unwrap();
+        let photos = dir.path().join("Photos");
+        std::fs::create_dir_all(photos.join("2017/10")).unwrap();
+        std::fs::write(photos.join("2017/10/IMG_0001.CR2"), "raw").unwrap();
+        std::fs::write(photos.join("2017/10/IMG_0001.JPG"), "jpg").unwrap();
+
+        let report = Reconciler::new(&catalog).reconcile_folder(catalog.folder(13).unwrap());
+        let entry = report
+            .entries
+            .iter()
+            .find(|entry| entry.file == Some(20))
+            .unwrap();
+        assert_eq!(entry.path, photos.join("2017/10/IMG_0001.CR2"));
+        assert_eq!(
+            entry.missing_sidecars,
+            vec![photos.join("2017/10/IMG_0001.xmp")]
+        );
+
+        // The sidecars are next to the file.
+        let image = catalog.image(30).unwrap();
+        assert_eq!(
+            catalog.image_path(image),
+            Some(photos.join("2017/10/IMG_0001.CR2"))
+        );
+        assert_eq!(
+            catalog.sidecar_paths(image),
+            vec![
+                photos.join("2017/10/IMG_0001.JPG"),
+                photos.join("2017/10/IMG_0001.xmp")
+            ]
+        );
+    }
+}
@@ -0,0 +1,274 @@
+/*
+ This Source Code Form is subject to the terms of the Mozilla Public
+ License, v. 2.0. If a copy of the MPL was not distributed with this
+ file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+use std::path::{Component, Path, PathBuf};
+
+use rusqlite::{params, Connection, OpenFlags};
+
+use crate::catalog::Catalog;
+use crate::folders::Folders;
+use crate::lrobject::LrId;
+
+/// Move a `RootFolder` to a new location.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RootRelocation {
+    /// The `RootFolder` id.
+    pub root: LrId,
+    /// The new absolute path.
+    pub absolute_path: PathBuf,
+}
+
+impl RootRelocation {
+    pub fn new<P>(root: LrId, absolute_path: P) -> RootRelocation
+    where
+        P: AsRef<Path>,
+    {
+        RootRelocation {
+            root,
+            absolute_path: absolute_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The path like stored in the catalog, with `/` and ending with
+    /// one.
+    fn catalog_path(path: &Path) -> String {
+        let mut path = path.to_string_lossy().replace('\\', "/");
+        if !path.ends_with('/') {
+            path.push('/');
+        }
+        path
+    }
+}
+
+/// Return the folders, of the roots in `relocations`, that don't exist
+/// in their new location. The new locations must be absolute.
+pub(crate) fn missing_folders(
+    folders: &Folders,
+    relocations: &[RootRelocation],
+) -> crate::Result<Vec<PathBuf>> {
+    if folders.is_empty() {
+        return Err(crate::Error::FoldersNotLoaded);
+    }
+    let mut missing = vec![];
+    for relocation in relocations {
+        if !relocation.absolute_path.is_absolute() {
+            return Err(crate::Error::InvalidPath(relocation.absolute_path.clone()));
+        }
+        folders
+            .find_root_folder(relocation.root)
+            .ok_or(crate::Error::UnknownRootFolder(relocation.root))?;
+        missing.extend(
+            folders
+                .folders()
+                .iter()
+                .filter(|folder| folder.root_folder == relocation.root)
+                .map(|folder| {
+                    relocation
+                        .absolute_path
+                        .join(folder.path_from_root.trim_start_matches('/'))
+                })
+                .filter(|path| !path.is_dir()),
+        );
+    }
+    Ok(missing)
+}
+
+/// Write `relocations` in the catalog at `destination`, in one
+/// transaction. `relativePathFromCatalog` is computed from the
+/// directory of `destination`.
+pub(crate) fn write_relocations(
+    destination: &Path,
+    relocations: &[RootRelocation],
+) -> crate::Result<()> {
+    // `destination` exists: canonicalize to get the absolute
+    // directory, even if `destination` is relative.
+    let catalog_dir = destination
+        .canonicalize()?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let mut conn = Connection::open_with_flags(
+        Catalog::path_uri(destination),
+        OpenFlags::SQLITE_OPEN_READ_WRITE
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    let tx = conn.transaction()?;
+    for relocation in relocations {
+        let relative = relative_path(&catalog_dir, &relocation.absolute_path)
+            .map(|path| RootRelocation::catalog_path(&path));
+        tx.execute(
+            "UPDATE AgLibraryRootFolder SET absolutePath = ?1, relativePathFromCatalog = ?2 \
+             WHERE id_local = ?3",
+            params![
+                RootRelocation::catalog_path(&relocation.absolute_path),
+                relative,
+                relocation.root
+            ],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// The path of `path` relative to the directory `base`. Both must be
+/// absolute. `None` if there is no common root, like on different
+/// drives.
+fn relative_path(base: &Path, path: &Path) -> Option<PathBuf> {
+    if !base.is_absolute() || !path.is_absolute() {
+        return None;
+    }
+    let base = base.components().collect::<Vec<_>>();
+    let path = path.components().collect::<Vec<_>>();
+    let common = base
+        .iter()
+        .zip(path.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    // Not even the prefix or root directory in common.
+    if common == 0 || matches!(base[..common], [Component::Prefix(_)]) {
+        return None;
+    }
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &path[common..] {
+        relative.push(component);
+    }
+    Some(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{relative_path, RootRelocation};
+    use crate::catalog::tests::open_test_catalog;
+    use crate::catalog::Catalog;
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path(
+                Path::new("/home/user/Catalog"),
+                Path::new("/home/user/Pictures")
+            ),
+            Some(PathBuf::from("../Pictures"))
+        );
+        assert_eq!(
+            relative_path(Path::new("/mnt/nas"), Path::new("/mnt/nas/Pictures/2017")),
+            Some(PathBuf::from("Pictures/2017"))
+        );
+        assert_eq!(
+            relative_path(Path::new("/mnt"), Path::new("/home")),
+            Some(PathBuf::from("../home"))
+        );
+        assert_eq!(relative_path(Path::new("mnt"), Path::new("/home")), None);
+    }
+
+    #[test]
+    fn test_relocate_roots() {
+        let (dir, catalog) = open_test_catalog("");
+        let path = catalog.path().to_path_buf();
+        drop(catalog);
+        // Like a user catalog.
+        let mut catalog = Catalog::new(&path);
+        catalog.open_read_only().unwrap();
+        assert!(matches!(
+            catalog.relocate_roots(&[], &dir.path().join("relocated.lrcat")),
+            Err(crate::Error::FoldersNotLoaded)
+        ));
+        catalog.load_folders().unwrap();
+        let photos = dir.path().join("Photos");
+        let copy = dir.path().join("relocated.lrcat");
+        let relocations = [RootRelocation::new(10, &photos)];
+
+        std::fs::create_dir_all(photos.join("2017")).unwrap();
+        match catalog.relocate_roots(&relocations, &copy) {
+            Err(crate::Error::MissingFolders(missing)) => {
+                assert_eq!(missing, vec![photos.join("2017/10/")]);
+            }
+            _ => unreachable!(),
+        }
+        assert!(!copy.exists());
+        assert!(matches!(
+            catalog.relocate_roots(&[RootRelocation::new(99, &photos)], &copy),
+            Err(crate::Error::UnknownRootFolder(99))
+        ));
+        assert!(matches!(
+            catalog.relocate_roots(&[RootRelocation::new(10, "Photos")], &copy),
+            Err(crate::Error::InvalidPath(ref p)) if p == Path::new("Photos")
+        ));
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let invalid = dir
+                .path()
+                .join(std::ffi::OsStr::from_bytes(b"relocated\xff.lrcat"));
+            std::fs::create_dir_all(photos.join("2017/10")).unwrap();
+            assert!(matches!(
+                catalog.relocate_roots(&relocations, &invalid),
+                Err(crate::Error::InvalidPath(_))
+            ));
+            std::fs::remove_dir(photos.join("2017/10")).unwrap();
+        }
+
+        std::fs::create_dir_all(photos.join("2017/10")).unwrap();
+        catalog
+            .relocate_roots(&relocations, &copy)
+            .expect("Couldn't relocate");
+        // Not twice.
+        assert!(catalog.relocate_roots(&relocations, &copy).is_err());
+
+        let mut relocated = Catalog::new(&copy);
+        relocated.open_read_only().unwrap();
+        let folders = relocated.load_folders().unwrap();
+        let root = folders.find_root_folder(10).unwrap();
+        assert_eq!(root.absolute_path, format!("{}/", photos.to_string_lossy()));
+        assert_eq!(root.relative_path_from_catalog.as_deref(), Some("Photos/"));
+        assert_eq!(
+            folders.folder_path(folders.find_folder(13).unwrap()),
+            Some(photos.join("2017/10/"))
+        );
+
+        // The original is untouched.
+        assert_eq!(
+            catalog.root_folder(10).unwrap().absolute_path,
+            "/home/user/Pictures/"
+        );
+    }
+
+    #[test]
+    fn test_relocate_relative_destination() {
+        let (dir, mut catalog) = open_test_catalog("");
+        catalog.load_folders().unwrap();
+        let photos = dir.path().join("Photos");
+        std::fs::create_dir_all(photos.join("2017/10")).unwrap();
+
+        // Relative, and would be a URI.
+        let copy = Path::new("file:relocated?mode=ro#1.lrcat");
+        let cwd = std::env::current_dir().unwrap();
+        std::env::set_current_dir(dir.path()).unwrap();
+        let result = catalog.relocate_roots(&[RootRelocation::new(10, &photos)], copy);
+        std::env::set_current_dir(cwd).unwrap();
+        result.expect("Couldn't relocate");
+
+        let copy = dir.path().join(copy);
+        assert!(copy.exists());
+        let mut relocated = Catalog::new(&copy);
+        relocated.open_read_only().unwrap();
+        let folders = relocated.load_folders().unwrap();
+        assert_eq!(
+            folders
+                .find_root_folder(10)
+                .unwrap()
+                .relative_path_from_catalog
+                .as_deref(),
+            Some("Photos/")
+        );
+    }
+}
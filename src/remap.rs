@@ -0,0 +1,154 @@
+/*
+ This Source Code Form is subject to the terms of the Mozilla Public
+ License, v. 2.0. If a copy of the MPL was not distributed with this
+ file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+use std::path::Path;
+
+/// A rule replacing the `from` prefix of a path by `to`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RemapRule {
+    /// The prefix to replace, normalized.
+    pub from: String,
+    /// The replacement.
+    pub to: String,
+}
+
+impl RemapRule {
+    /// Create a rule replacing `from` by `to`.
+    pub fn new(from: &str, to: &str) -> RemapRule {
+        RemapRule {
+            from: PathRemap::normalize(from).trim_end_matches('/').to_string(),
+            to: to.trim_end_matches(['/', '\\']).to_string(),
+        }
+    }
+
+    /// Parse a rule written `FROM=TO`. `FROM` can't be empty or the
+    /// root, that would match every path.
+    pub fn parse(s: &str) -> crate::Result<RemapRule> {
+        s.split_once('=')
+            .map(|(from, to)| RemapRule::new(from.trim(), to.trim()))
+            .filter(|rule| !rule.from.is_empty())
+            .ok_or_else(|| crate::Error::InvalidRemapRule(s.to_string()))
+    }
+
+    /// Apply the rule to the normalized `path`. Return `None` if it
+    /// doesn't match.
+    fn apply(&self, path: &str) -> Option<String> {
+        let rest = path.strip_prefix(&self.from)?;
+        if rest.is_empty() || rest.starts_with('/') {
+            Some(format!("{}{rest}", self.to))
+        } else {
+            None
+        }
+    }
+}
+
+/// Rewrite the paths stored in the catalog to where the files are
+/// now, like when the catalog comes from another computer. The first
+/// matching rule is applied to the path normalized to use `/` and
+/// upper case drive letters. Rules only match whole path components.
+#[derive(Clone, Debug, Default)]
+pub struct PathRemap {
+    rules: Vec<RemapRule>,
+}
+
+impl PathRemap {
+    pub fn new() -> PathRemap {
+        PathRemap::default()
+    }
+
+    /// Load the rules from the file at `path`: one `FROM=TO` rule per
+    /// line. Empty lines and lines starting with `#` are ignored.
+    pub fn from_file<P>(path: P) -> crate::Result<PathRemap>
+    where
+        P: AsRef<Path>,
+    {
+        let mut remap = PathRemap::new();
+        for line in std::fs::read_to_string(path)?.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            remap.add_rule(RemapRule::parse(line)?);
+        }
+        Ok(remap)
+    }
+
+    /// Return `true` if there is no rule.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The rules.
+    pub fn rules(&self) -> &[RemapRule] {
+        &self.rules
+    }
+
+    /// Add a rule. It applies after the ones already added.
+    pub fn add_rule(&mut self, rule: RemapRule) {
+        self.rules.push(rule);
+    }
+
+    /// Normalize `path`: `\` are replaced by `/` and a drive letter is
+    /// put in upper case.
+    pub fn normalize(path: &str) -> String {
+        let mut path = path.replace('\\', "/");
+        let bytes = path.as_bytes();
+        if bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic() {
+            path[..1].make_ascii_uppercase();
+        }
+        path
+    }
+
+    /// Remap `path`. If no rule match, `path` is returned unchanged.
+    pub fn remap(&self, path: &str) -> String {
+        if self.rules.is_empty() {
+            return path.to_string();
+        }
+        let normalized = PathRemap::normalize(path);
+        self.rules
+            .iter()
+            .find_map(|rule| rule.apply(&normalized))
+            .unwrap_or_else(|| path.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PathRemap, RemapRule};
+
+    #[test]
+    fn test_remap() {
+        let mut remap = PathRemap::new();
+        assert_eq!(remap.remap("c:\\Photos\\2017\\"), "c:\\Photos\\2017\\");
+
+        remap.add_rule(RemapRule::parse("c:\\Photos\\=/mnt/nas/Photos/").unwrap());
+        remap.add_rule(RemapRule::parse("/Volumes/External = /mnt/nas/External").unwrap());
+        remap.add_rule(RemapRule::new("C:/", "/mnt/nas/c"));
+
+        assert_eq!(remap.remap("C:/Photos/2017/"), "/mnt/nas/Photos/2017/");
+        assert_eq!(remap.remap("c:\\Photos\\2017\\"), "/mnt/nas/Photos/2017/");
+        assert_eq!(remap.remap("C:/Photos"), "/mnt/nas/Photos");
+        assert_eq!(remap.remap("C:/PhotosOld/"), "/mnt/nas/c/PhotosOld/");
+        assert_eq!(
+            remap.remap("/Volumes/External/2017/IMG_0001.CR2"),
+            "/mnt/nas/External/2017/IMG_0001.CR2"
+        );
+        assert_eq!(remap.remap("/Volumes/Other/"), "/Volumes/Other/");
+        assert_eq!(remap.remap("d:\\Other\\"), "d:\\Other\\");
+
+        assert!(RemapRule::parse("/Volumes").is_err());
+        assert!(RemapRule::parse("=/mnt").is_err());
+        assert!(RemapRule::parse("/=/mnt").is_err());
+        assert!(RemapRule::parse(" \\ =/mnt").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("remap.txt");
+        std::fs::write(&file, "# Moved drives\n\nD:/=/mnt/d\n").unwrap();
+        let remap = PathRemap::from_file(&file).unwrap();
+        assert_eq!(remap.rules(), &[RemapRule::new("D:", "/mnt/d")]);
+        assert_eq!(remap.remap("d:\\2017\\"), "/mnt/d/2017/");
+    }
+}
@@ -0,0 +1,312 @@
+/*
+ This Source Code Form is subject to the terms of the Mozilla Public
+ License, v. 2.0. If a copy of the MPL was not distributed with this
+ file, You can obtain one at http://mozilla.org/MPL/2.0/.
+*/
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use rusqlite::Connection;
+
+use crate::catalog::{Catalog, CatalogVersion, SnapshotDir};
+use crate::collections::Collection;
+use crate::folders::{Folder, Folders, RootFolder};
+use crate::fromdb::RowError;
+use crate::images::Image;
+use crate::keywords::Keyword;
+use crate::libraryfiles::LibraryFile;
+use crate::lrobject::{LrId, ObjectIndex};
+
+/// A pool of read-only connections to a catalog.
+pub(crate) struct ConnectionPool {
+    /// The path of the database.
+    path: PathBuf,
+    /// Whether the database can be open immutable.
+    immutable: bool,
+    /// The connections not in use.
+    idle: Mutex<Vec<Connection>>,
+    /// The snapshot directory the database is in, if any. After
+    /// `idle` to be dropped last.
+    _snapshot: Option<SnapshotDir>,
+}
+
+impl ConnectionPool {
+    /// How many idle connections are kept.
+    const MAX_IDLE: usize = 8;
+
+    pub(crate) fn new(
+        path: PathBuf,
+        immutable: bool,
+        snapshot: Option<SnapshotDir>,
+    ) -> ConnectionPool {
+        ConnectionPool {
+            path,
+            immutable,
+            idle: Mutex::new(vec![]),
+            _snapshot: snapshot,
+        }
+    }
+
+    /// Get a connection, opening a new one if none is idle. It is
+    /// returned to the pool when dropped.
+    pub(crate) fn get(&self) -> crate::Result<PooledConnection<'_>> {
+        let conn = self
+            .idle
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .pop();
+        let conn = match conn {
+            Some(conn) => conn,
+            None => Catalog::open_connection(&self.path, self.immutable)?,
+        };
+        Ok(PooledConnection {
+            pool: self,
+            conn: Some(conn),
+        })
+    }
+}
+
+/// A `Connection` borrowed from a `ConnectionPool`.
+pub(crate) struct PooledConnection<'a> {
+    pool: &'a ConnectionPool,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            let mut idle = self.pool.idle.lock().unwrap_or_else(|err| err.into_inner());
+            if idle.len() < ConnectionPool::MAX_IDLE {
+                idle.push(conn);
+            }
+        }
+    }
+}
+
+/// A read-only catalog that can be shared across threads. The loaded
+/// objects are shared, and queries use a pool of read-only
+/// connections. Cloning is cheap.
+///
+/// Obtained with `Catalog::into_shared()` or `SharedCatalog::open()`.
+#[derive(Clone)]
+pub struct SharedCatalog {
+    pub(crate) path: PathBuf,
+    pub(crate) version: String,
+    pub(crate) catalog_version: CatalogVersion,
+    pub(crate) root_keyword_id: LrId,
+    pub(crate) keywords: Arc<BTreeMap<LrId, Keyword>>,
+    pub(crate) keyword_uuids: Arc<HashMap<String, LrId>>,
+    pub(crate) folders: Arc<Folders>,
+    pub(crate) images: Arc<Vec<Image>>,
+    pub(crate) image_index: Arc<ObjectIndex>,
+    pub(crate) libfiles: Arc<Vec<LibraryFile>>,
+    pub(crate) libfile_index: Arc<ObjectIndex>,
+    pub(crate) collections: Arc<Vec<Collection>>,
+    pub(crate) row_errors: Arc<Vec<RowError>>,
+    pub(crate) pool: Arc<ConnectionPool>,
+}
+
+impl SharedCatalog {
+    /// Open the catalog at `path` read-only, and load all the objects.
+    /// Like `Catalog::open_read_only()` it fails with
+    /// `Error::CatalogLocked` if Lightroom has the catalog open, and
+    /// like `Catalog::validate()` if the version isn't supported.
+    pub fn open<P>(path: P) -> crate::Result<SharedCatalog>
+    where
+        P: AsRef<Path>,
+    {
+        let mut catalog = Catalog::new(path);
+        catalog.open_read_only()?;
+        catalog.validate()?;
+        let row_errors = catalog.load_all(|_| {})?;
+
+        let mut shared = catalog.into_shared()?;
+        shared.row_errors = Arc::new(row_errors);
+        Ok(shared)
+    }
+
+    /// Catalog path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The version string
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// The catalog version
+    pub fn catalog_version(&self) -> CatalogVersion {
+        self.catalog_version
+    }
+
+    /// Id for the root (top level) keyword
+    pub fn root_keyword_id(&self) -> LrId {
+        self.root_keyword_id
+    }
+
+    /// The keywords, mapped in the local `LrId`
+    pub fn keywords(&self) -> &BTreeMap<LrId, Keyword> {
+        &self.keywords
+    }
+
+    /// The folders.
+    pub fn folders(&self) -> &Folders {
+        &self.folders
+    }
+
+    /// The images.
+    pub fn images(&self) -> &[Image] {
+        &self.images
+    }
+
+    /// The library files.
+    pub fn libfiles(&self) -> &[LibraryFile] {
+        &self.libfiles
+    }
+
+    /// The collections.
+    pub fn collections(&self) -> &[Collection] {
+        &self.collections
+    }
+
+    /// The rows that failed to load in `open()`.
+    pub fn row_errors(&self) -> &[RowError] {
+        &self.row_errors
+    }
+
+    /// Get the image with `id`.
+    pub fn image(&self, id: LrId) -> Option<&Image> {
+        self.image_index.get(&self.images, id)
+    }
+
+    /// Get the library file with `id`.
+    pub fn library_file(&self, id: LrId) -> Option<&LibraryFile> {
+        self.libfile_index.get(&self.libfiles, id)
+    }
+
+    /// Get the folder with `id`.
+    pub fn folder(&self, id: LrId) -> Option<&Folder> {
+        self.folders.find_folder(id)
+    }
+
+    /// Get the root folder with `id`.
+    pub fn root_folder(&self, id: LrId) -> Option<&RootFolder> {
+        self.folders.find_root_folder(id)
+    }
+
+    /// Get the keyword with `id`.
+    pub fn keyword(&self, id: LrId) -> Option<&Keyword> {
+        self.keywords.get(&id)
+    }
+
+    /// Get the keyword with `uuid`.
+    pub fn keyword_by_uuid(&self, uuid: &str) -> Option<&Keyword> {
+        self.keyword_uuids
+            .get(uuid)
+            .and_then(|id| self.keywords.get(id))
+    }
+
+    /// Run `f` with a read-only connection to the catalog, taken from
+    /// the pool.
+    pub fn with_connection<F, T>(&self, f: F) -> crate::Result<T>
+    where
+        F: FnOnce(&Connection) -> crate::Result<T>,
+    {
+        let conn = self.pool.get()?;
+        f(&conn)
+    }
+
+    /// Return the of images in the given collection.
+    /// Not to be confused with Content.
+    pub fn images_for_collection(&self, collection_id: LrId) -> crate::Result<Vec<LrId>> {
+        self.with_connection(|conn| {
+            Catalog::query_images_for_collection(conn, self.catalog_version, collection_id)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SharedCatalog;
+    use crate::catalog::tests::open_test_catalog;
+    use crate::lrobject::LrObject;
+
+    #[test]
+    fn test_shared_catalog() {
+        fn is_send_sync<T: Send + Sync>() {}
+        is_send_sync::<SharedCatalog>();
+
+        let (_dir, catalog) = open_test_catalog("");
+        let path = catalog.path().to_path_buf();
+        drop(catalog);
+
+        let catalog = SharedCatalog::open(&path).expect("Couldn't open shared catalog");
+        assert_eq!(catalog.images().len(), 4);
+        assert_eq!(
+            catalog.keyword(3).map(|k| k.name.as_str()),
+            Some("Montréal")
+        );
+
+        let threads = (0..4)
+            .map(|_| {
+                let catalog = catalog.clone();
+                std::thread::spawn(move || {
+                    let ids = catalog.images_for_collection(42).unwrap();
+                    ids.iter()
+                        .filter_map(|id| catalog.image(*id))
+                        .map(|image| image.id())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), vec![30, 31]);
+        }
+        assert!(catalog.pool.idle.lock().unwrap().len() <= 4);
+
+        // Lightroom has it open.
+        std::fs::write(path.with_extension("lrcat-lock"), "").unwrap();
+        assert!(SharedCatalog::open(&path).is_err());
+        #[cfg(feature = "snapshot")]
+        {
+            let mut catalog = crate::Catalog::new(&path);
+            catalog.open_snapshot().expect("Couldn't open snapshot");
+            catalog.load_images().unwrap();
+            let catalog = catalog.into_shared().expect("Couldn't share catalog");
+            assert_eq!(catalog.images().len(), 4);
+            assert_eq!(catalog.images_for_collection(42).unwrap(), vec![30, 31]);
+        }
+    }
+
+    #[test]
+    fn test_shared_catalog_too_new() {
+        let (_dir, catalog) = open_test_catalog("");
+        let path = catalog.path().to_path_buf();
+        drop(catalog);
+        // A Lightroom Classic catalog.
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .execute(
+                "UPDATE Adobe_variablesTable SET value = '1300025' WHERE name = 'Adobe_DBVersion'",
+                [],
+            )
+            .unwrap();
+
+        assert!(matches!(
+            SharedCatalog::open(&path),
+            Err(crate::Error::VersionTooNew(ref v)) if v == "1300025"
+        ));
+    }
+}